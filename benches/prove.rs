@@ -0,0 +1,51 @@
+//! Times each phase of proving the bundled 9x9 example with a real prover,
+//! to see how circuit changes move the numbers:
+//!
+//! ```text
+//! cargo bench --bench prove
+//! ```
+//!
+//! Runs at the smallest `k` the board fits in. Params and keys are generated
+//! once, outside the timed loops.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{keygen_pk, keygen_vk},
+    poly::commitment::Params,
+};
+use halo2_sudoku::{
+    min_k, packed_instance, parse_line, parse_puzzle, prove_with_params, to_field_grid,
+    verify_with_params, MyCircuit,
+};
+
+const PUZZLE: &str =
+    "001004090400000107080700004900010800000807000008060001800005010605000009010900400";
+
+fn prove_phases(c: &mut Criterion) {
+    let clues = parse_puzzle(PUZZLE).unwrap();
+    let public = packed_instance(&to_field_grid::<Fp>(parse_line(PUZZLE).unwrap()));
+    let circuit = MyCircuit::<Fp>::from_clues(&clues).unwrap();
+    let k = min_k(&circuit);
+
+    let params: Params<EqAffine> = Params::new(k);
+    let vk = keygen_vk(&params, &circuit).unwrap();
+    let pk = keygen_pk(&params, vk.clone(), &circuit).unwrap();
+    let proof = prove_with_params(&params, &pk, circuit.clone(), &public).unwrap();
+
+    let mut group = c.benchmark_group(format!("9x9 k={}", k));
+    group.sample_size(10);
+    group.bench_function("witness", |b| {
+        b.iter(|| MyCircuit::<Fp>::from_clues(&clues).unwrap())
+    });
+    group.bench_function("create_proof", |b| {
+        b.iter(|| prove_with_params(&params, &pk, circuit.clone(), &public).unwrap())
+    });
+    group.bench_function("verify_proof", |b| {
+        b.iter(|| verify_with_params(&params, &vk, &proof, &public).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, prove_phases);
+criterion_main!(benches);
@@ -0,0 +1,180 @@
+//! Compares the two ways the 9x9 circuit can check that a unit holds each
+//! digit once, `UniquenessMode::Permutation` (the default) and the unsound
+//! `UniquenessMode::SumProduct`, on the same puzzle and witness:
+//!
+//! ```text
+//! cargo bench --bench uniqueness
+//! ```
+//!
+//! Prints `k`, the gate degree, the gate and lookup counts and the time of
+//! one proof for both side by side, then times proving for each with
+//! criterion.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    dev::MockProver,
+    pasta::{EqAffine, Fp},
+    plonk::{keygen_pk, keygen_vk, Circuit, ConstraintSystem, Error, ProvingKey},
+    poly::commitment::Params,
+};
+use halo2_sudoku::{
+    packed_instance, parse_line, parse_puzzle, prove_with_params, solution_witness, solve_puzzle,
+    to_field_grid, SudokuChip, SudokuConfig, UniquenessMode,
+};
+use std::{marker::PhantomData, time::Instant};
+
+const PUZZLE: &str =
+    "001004090400000107080700004900010800000807000008060001800005010605000009010900400";
+
+/// The uniqueness mode a [`ModeCircuit`] is configured with.
+trait Mode {
+    const NAME: &'static str;
+    const MODE: UniquenessMode;
+}
+
+struct Permutation;
+
+impl Mode for Permutation {
+    const NAME: &'static str = "permutation";
+    const MODE: UniquenessMode = UniquenessMode::Permutation;
+}
+
+struct SumProduct;
+
+impl Mode for SumProduct {
+    const NAME: &'static str = "sum/product";
+    const MODE: UniquenessMode = UniquenessMode::SumProduct;
+}
+
+/// The 9x9 circuit with its units checked in mode `M`; everything else is
+/// as in `MyCircuit`.
+struct ModeCircuit<M> {
+    solution: Vec<Vec<Fp>>,
+    _mode: PhantomData<M>,
+}
+
+impl<M> Clone for ModeCircuit<M> {
+    fn clone(&self) -> Self {
+        Self {
+            solution: self.solution.clone(),
+            _mode: PhantomData,
+        }
+    }
+}
+
+impl<M: Mode> Circuit<Fp> for ModeCircuit<M> {
+    type Config = SudokuConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            solution: Vec::new(),
+            _mode: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        SudokuChip::configure_with_uniqueness(meta, M::MODE)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        SudokuChip::construct(config).assign(layouter, &self.solution)?;
+        Ok(())
+    }
+}
+
+/// Everything one mode needs to prove the example, plus the figures that
+/// do not need a timer.
+struct Setup<M> {
+    circuit: ModeCircuit<M>,
+    public: Vec<Vec<Fp>>,
+    k: u32,
+    degree: usize,
+    gates: usize,
+    lookups: usize,
+    params: Params<EqAffine>,
+    pk: ProvingKey<EqAffine>,
+}
+
+fn setup<M: Mode>() -> Setup<M> {
+    let clues = parse_puzzle(PUZZLE).unwrap();
+    let full = solve_puzzle(&clues).unwrap();
+    let circuit = ModeCircuit::<M> {
+        solution: solution_witness(&clues, &full),
+        _mode: PhantomData,
+    };
+    let public = packed_instance(&to_field_grid::<Fp>(parse_line(PUZZLE).unwrap()));
+
+    let mut cs = ConstraintSystem::<Fp>::default();
+    ModeCircuit::<M>::configure(&mut cs);
+
+    // as `min_k` does for `MyCircuit`: the smallest k synthesis fits in
+    let mut k = 1;
+    while 1 << k <= cs.blinding_factors() + 1 {
+        k += 1;
+    }
+    loop {
+        match MockProver::run(k, &circuit, public.clone()) {
+            Ok(_) => break,
+            Err(Error::NotEnoughRowsAvailable { .. }) | Err(Error::InstanceTooLarge) => k += 1,
+            Err(e) => panic!("synthesis failed at k = {}: {:?}", k, e),
+        }
+    }
+
+    let params = Params::new(k);
+    let vk = keygen_vk(&params, &circuit).unwrap();
+    let pk = keygen_pk(&params, vk, &circuit).unwrap();
+
+    Setup {
+        circuit,
+        public,
+        k,
+        degree: cs.degree(),
+        gates: cs.gates().len(),
+        lookups: cs.lookups().len(),
+        params,
+        pk,
+    }
+}
+
+impl<M: Mode> Setup<M> {
+    fn prove(&self) -> Vec<u8> {
+        prove_with_params(&self.params, &self.pk, self.circuit.clone(), &self.public).unwrap()
+    }
+
+    fn row(&self) -> String {
+        let start = Instant::now();
+        self.prove();
+        format!(
+            "{:<12} {:>3} {:>7} {:>6} {:>8} {:>10.1?}",
+            M::NAME,
+            self.k,
+            self.degree,
+            self.gates,
+            self.lookups,
+            start.elapsed()
+        )
+    }
+}
+
+fn compare_modes(c: &mut Criterion) {
+    let permutation = setup::<Permutation>();
+    let sum_product = setup::<SumProduct>();
+
+    println!(
+        "{:<12} {:>3} {:>7} {:>6} {:>8} {:>10}",
+        "mode", "k", "degree", "gates", "lookups", "one proof"
+    );
+    println!("{}", permutation.row());
+    println!("{}", sum_product.row());
+
+    let mut group = c.benchmark_group("9x9 uniqueness");
+    group.sample_size(10);
+    group.bench_function(Permutation::NAME, |b| b.iter(|| permutation.prove()));
+    group.bench_function(SumProduct::NAME, |b| b.iter(|| sum_product.prove()));
+    group.finish();
+}
+
+criterion_group!(benches, compare_modes);
+criterion_main!(benches);
@@ -0,0 +1,47 @@
+//! Solves the puzzle in a file, proves the solution with a real prover,
+//! writes the proof bytes out and checks them with the verifier:
+//!
+//! ```text
+//! cargo run --example prove_file -- examples/puzzle.txt out.proof
+//! ```
+//!
+//! The puzzle is one line of 81 cells, digits for clues and `0` or `.` for
+//! blanks, as read by `parse_puzzle`.
+
+use halo2_proofs::{pasta::Fp, plonk::keygen_vk, poly::commitment::Params};
+use halo2_sudoku::{min_k, parse_puzzle, prove, verify, Grid, MyCircuit};
+use std::{env, fs, process};
+
+fn run(puzzle_path: &str, proof_path: &str) -> Result<(), String> {
+    let text = fs::read_to_string(puzzle_path)
+        .map_err(|e| format!("cannot read {}: {}", puzzle_path, e))?;
+    let clues = parse_puzzle(text.trim()).map_err(|e| format!("bad puzzle: {}", e))?;
+    println!("{:#}", Grid(clues));
+
+    let (circuit, instance) =
+        MyCircuit::<Fp>::build(&clues).map_err(|e| format!("cannot build circuit: {}", e))?;
+    let k = min_k(&circuit);
+
+    let proof = prove(k, &circuit, &instance[0]).map_err(|e| format!("proving failed: {:?}", e))?;
+    fs::write(proof_path, &proof).map_err(|e| format!("cannot write {}: {}", proof_path, e))?;
+    println!("wrote a {} byte proof to {}", proof.len(), proof_path);
+
+    let vk = keygen_vk(&Params::new(k), &circuit).map_err(|e| format!("keygen failed: {:?}", e))?;
+    let proof = fs::read(proof_path).map_err(|e| format!("cannot read {}: {}", proof_path, e))?;
+    verify(k, &vk, &instance[0], &proof).map_err(|e| format!("verification failed: {:?}", e))?;
+    println!("proof verifies");
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let [_, puzzle_path, proof_path] = &args[..] else {
+        eprintln!("usage: prove_file <puzzle.txt> <out.proof>");
+        process::exit(2);
+    };
+
+    if let Err(e) = run(puzzle_path, proof_path) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
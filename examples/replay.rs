@@ -0,0 +1,117 @@
+//! Re-proves every bundled (puzzle, solution) pair in `puzzles.txt` with a
+//! real prover and reports failures by puzzle index:
+//!
+//! ```text
+//! cargo run --example replay
+//! ```
+
+use halo2_sudoku::{parse_line, to_field_grid, SudokuProver};
+
+const PUZZLES: &str = include_str!("puzzles.txt");
+
+const K: u32 = 7;
+
+struct Case {
+    name: &'static str,
+    puzzle: Vec<Vec<u64>>,
+    solution: Vec<Vec<u64>>,
+}
+
+fn cases() -> Vec<Case> {
+    PUZZLES
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[..] {
+                [name, puzzle, solution] => Case {
+                    name,
+                    puzzle: parse_line(puzzle).unwrap(),
+                    solution: parse_line(solution).unwrap(),
+                },
+                _ => panic!("malformed case: {:?}", line),
+            }
+        })
+        .collect()
+}
+
+/// Proves and verifies one case, describing the first step that fails.
+fn replay(case: &Case) -> Result<(), String> {
+    // the witness is zero wherever the puzzle has a given
+    let witness: Vec<Vec<u64>> = case
+        .puzzle
+        .iter()
+        .zip(&case.solution)
+        .map(|(givens, row)| {
+            givens
+                .iter()
+                .zip(row)
+                .map(|(&given, &value)| if given == 0 { value } else { 0 })
+                .collect()
+        })
+        .collect();
+
+    let prover = SudokuProver::new(K, to_field_grid(case.puzzle.clone()))
+        .map_err(|e| format!("keygen failed: {:?}", e))?;
+    let proof = prover
+        .prove(to_field_grid(witness))
+        .map_err(|e| format!("proving failed: {:?}", e))?;
+    prover
+        .verify(&proof)
+        .map_err(|e| format!("verification failed: {:?}", e))
+}
+
+fn main() {
+    let mut failed = 0;
+    for (index, case) in cases().iter().enumerate() {
+        match replay(case) {
+            Ok(()) => println!("puzzle {} ({}): ok", index, case.name),
+            Err(e) => {
+                failed += 1;
+                println!("puzzle {} ({}): {}", index, case.name, e);
+            }
+        }
+    }
+
+    if failed > 0 {
+        eprintln!("{} puzzle(s) failed", failed);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cases, replay};
+
+    #[test]
+    fn bundled_cases_cover_variety() {
+        let cases = cases();
+        let clues = |puzzle: &[Vec<u64>]| puzzle.iter().flatten().filter(|&&v| v != 0).count();
+
+        let hard = cases.iter().find(|case| case.name == "hard").unwrap();
+        assert_eq!(clues(&hard.puzzle), 17);
+
+        let symmetric = cases.iter().find(|case| case.name == "symmetric").unwrap();
+        let cells: Vec<u64> = symmetric.puzzle.iter().flatten().copied().collect();
+        assert!(cells
+            .iter()
+            .zip(cells.iter().rev())
+            .all(|(a, b)| (*a == 0) == (*b == 0)));
+
+        assert!(cases.iter().any(|case| case.name == "easy"));
+    }
+
+    #[test]
+    fn bundled_puzzles_replay() {
+        let failures: Vec<String> = cases()
+            .iter()
+            .enumerate()
+            .filter_map(|(index, case)| {
+                replay(case)
+                    .err()
+                    .map(|e| format!("puzzle {} ({}): {}", index, case.name, e))
+            })
+            .collect();
+        assert!(failures.is_empty(), "{:#?}", failures);
+    }
+}
@@ -0,0 +1,15 @@
+//! Feeds arbitrary bytes, valid UTF-8 or not, to `parse_puzzle`; it must
+//! return `Ok` or `Err` and never panic. Anything it accepts must be a grid
+//! of blanks and digits.
+
+#![no_main]
+
+use halo2_sudoku::parse_puzzle;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let line = String::from_utf8_lossy(data);
+    if let Ok(grid) = parse_puzzle(&line) {
+        assert!(grid.iter().flatten().all(|&value| value <= 9));
+    }
+});
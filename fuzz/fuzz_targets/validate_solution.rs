@@ -0,0 +1,15 @@
+//! Feeds arbitrary 81-byte grids, read row-major, to `validate_solution`; it
+//! must return `Ok` or `Err` and never panic, whatever the cells hold.
+
+#![no_main]
+
+use halo2_sudoku::validate_solution;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: [u8; 81]| {
+    let mut grid = [[0; 9]; 9];
+    for (row, cells) in grid.iter_mut().zip(data.chunks(9)) {
+        row.copy_from_slice(cells);
+    }
+    let _ = validate_solution(&grid);
+});
@@ -0,0 +1,4363 @@
+use halo2_gadgets::poseidon::{
+    primitives::{self as poseidon, ConstantLength, P128Pow5T3},
+    Hash, Pow5Chip, Pow5Config,
+};
+use halo2_proofs::{
+    arithmetic::{Field, FieldExt},
+    circuit::*,
+    pasta::Fp,
+    plonk::*,
+    poly::Rotation,
+};
+use std::{
+    collections::{BTreeSet, HashMap},
+    marker::PhantomData,
+};
+
+use crate::{
+    error::SudokuError,
+    io::{check_instance, packed_instance, solution_witness, Grid},
+    solver::solve_puzzle,
+};
+
+/// A grid position as `(row, col)`, both in `0..N` on an `N x N` board.
+pub type Cell = (usize, usize);
+
+/// The four cells around a quadruple clue.
+pub type Cell4 = [Cell; 4];
+
+/// One cell of a witness for [`SudokuChip::assign_entries`], saying
+/// explicitly whether it is a clue rather than leaving that to a zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entry<F> {
+    /// Copied from the instance: a clue of the puzzle.
+    Given,
+    /// Witnessed as is, zero included.
+    Filled(F),
+}
+
+impl<F: FieldExt> Entry<F> {
+    /// Reads a zero-at-clue witness grid, as [`MyCircuit`] holds, with zero
+    /// as [`Entry::Given`].
+    pub fn from_zero_blanks(solution: &[Vec<F>]) -> Vec<Vec<Self>> {
+        solution
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&value| {
+                        if value == F::zero() {
+                            Entry::Given
+                        } else {
+                            Entry::Filled(value)
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// One of the gate sets a [`SudokuConfig`] can be built with. A config
+/// records every one it has, see [`SudokuConfig::versions`], so provers and
+/// verifiers can compare them to check they agree on the circuit before
+/// exchanging proofs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CircuitVersion {
+    /// Rows, columns, boxes, the `1..=N` range check and the binding of
+    /// every nonzero instance cell to the grid.
+    Classic,
+    /// `Classic` plus kropki dot gates.
+    Kropki,
+    /// `Classic` plus even/odd cell gates.
+    Parity,
+    /// `Classic` plus quadruple clue gates.
+    Quadruple,
+    /// `Classic` plus one-of-each gates on both main diagonals (X-Sudoku).
+    Diagonal,
+    /// `Classic` plus killer cage sum gates.
+    Killer,
+    /// `Classic` plus anti-knight and/or anti-king gates.
+    AntiMove,
+    /// `Classic` plus pencil-mark candidate gates.
+    Candidates,
+}
+
+/// Per-line statistics that [`SudokuChip::configure_aggregates`] can reveal as
+/// public outputs, nine values each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineAggregate {
+    /// The sum of each grid row, top to bottom.
+    RowSums,
+    /// The sum of each grid column, left to right.
+    ColumnSums,
+}
+
+/// How the rows, columns and boxes gates check that a unit holds every
+/// digit once. The digits are `1..=N` on an `N x N` board, or the config's
+/// alphabet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UniquenessMode {
+    /// The `N` cells sum and multiply to what the digits do, 45 and 9! on
+    /// the classic board. Cheap, but not sound: `{1, 2, 4, 4, 4, 5, 7, 9, 9}`
+    /// passes both 9x9 checks. Only available through
+    /// [`SudokuChip::configure_with_uniqueness`].
+    SumProduct,
+    /// For every `k` in `1..=N`, the cells' `k`-th powers sum to the digits'.
+    /// By Newton's identities this pins the multiset of the cells to the
+    /// digits, at `N` degree-up-to-`N` constraints per unit.
+    Permutation,
+}
+
+/// Where [`SudokuChip`] stores the grid, chosen with
+/// [`SudokuChip::configure_with_layout`]. Fewer columns cost more rows, and
+/// so possibly a larger `k`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// One advice column per grid row, the row's cells at rotations
+    /// `0..N`. The default.
+    Wide,
+    /// The `N * N` cells row-major over `columns` advice columns, which must
+    /// divide `N`: cell `i` is in column `i % columns` at rotation
+    /// `i / columns`. `columns: 1` puts the whole grid in one column.
+    Packed { columns: usize },
+}
+
+impl Layout {
+    /// The advice column index and rotation of grid cell `(row, col)` on an
+    /// `n x n` board.
+    pub fn place(self, n: usize, (row, col): Cell) -> (usize, usize) {
+        match self {
+            Layout::Wide => (row, col),
+            Layout::Packed { columns } => {
+                let index = row * n + col;
+                (index % columns, index / columns)
+            }
+        }
+    }
+
+    /// The advice columns one grid takes on an `n x n` board.
+    pub fn columns(self, n: usize) -> usize {
+        match self {
+            Layout::Wide => n,
+            Layout::Packed { columns } => columns,
+        }
+    }
+
+    /// The region rows one grid takes on an `n x n` board.
+    pub fn rows(self, n: usize) -> usize {
+        n * n / self.columns(n)
+    }
+}
+
+/// The columns and selectors `SudokuChip::configure` allocates for an
+/// `N x N` board with `BOX_ROWS x BOX_COLS` boxes.
+#[derive(Debug, Clone)]
+pub struct SudokuConfig<
+    const N: usize = 9,
+    const BOX_ROWS: usize = 3,
+    const BOX_COLS: usize = BOX_ROWS,
+> {
+    always_enabled: Selector,
+    only_first_enabled: Selector,
+
+    /// The grid, placed as `layout` says.
+    advice: Vec<Column<Advice>>,
+    /// The puzzle, row-major: grid cell `(row, col)` is at `row * N + col`.
+    instance: Column<Instance>,
+    /// A copy of every instance cell, laid out like `advice`, so the givens
+    /// can be checked by a gate without depending on where the region sits.
+    givens: Vec<Column<Advice>>,
+    /// The table every grid cell is looked up in, filled with `alphabet`.
+    digits: TableColumn,
+    /// The `N` digits every unit holds once: `1..=N`, unless configured with
+    /// [`SudokuChip::configure_alphabet`].
+    alphabet: Vec<u64>,
+
+    versions: BTreeSet<CircuitVersion>,
+    uniqueness: UniquenessMode,
+    layout: Layout,
+
+    /// Pairs of cells that must differ, from
+    /// [`SudokuChip::configure_anti_moves`]. Pair `i` witnesses the inverse
+    /// of its difference in `inverses[i / N]` at block row `i % N`.
+    inequalities: Vec<(Cell, Cell)>,
+    inverses: Vec<Column<Advice>>,
+}
+
+/// What a [`SudokuConfig`] allocates, for placing the chip next to others and
+/// choosing `k`. See [`SudokuConfig::layout_info`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutInfo {
+    /// The grid's columns, the copied givens' and any the variants added.
+    pub advice_columns: usize,
+    pub instance_columns: usize,
+    /// Region rows one grid takes; [`SudokuChip::assign_batch`] stacks one
+    /// such block per grid.
+    pub rows_used: usize,
+    pub selectors: usize,
+}
+
+impl<const N: usize, const BOX_ROWS: usize, const BOX_COLS: usize>
+    SudokuConfig<N, BOX_ROWS, BOX_COLS>
+{
+    /// `Classic`, plus every variant a `configure_*` call added a gate for.
+    pub fn versions(&self) -> &BTreeSet<CircuitVersion> {
+        &self.versions
+    }
+
+    pub fn uniqueness(&self) -> UniquenessMode {
+        self.uniqueness
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// The advice column and block row holding grid cell `cell`.
+    fn position(&self, cell: Cell) -> (Column<Advice>, usize) {
+        let (column, row) = self.layout.place(N, cell);
+        (self.advice[column], row)
+    }
+
+    /// The column of `givens` at the same place as [`Self::position`].
+    fn given_position(&self, cell: Cell) -> (Column<Advice>, usize) {
+        let (column, row) = self.layout.place(N, cell);
+        (self.givens[column], row)
+    }
+
+    /// The columns and rows this config's gates use, read off the config
+    /// without synthesizing anything. Columns other chips added to the same
+    /// `ConstraintSystem` are not counted, nor is the digits lookup table,
+    /// which takes `N` rows of one table column.
+    pub fn layout_info(&self) -> LayoutInfo {
+        LayoutInfo {
+            advice_columns: self.advice.len() + self.givens.len() + self.inverses.len(),
+            instance_columns: 1,
+            rows_used: self.layout.rows(N),
+            selectors: 2,
+        }
+    }
+}
+
+/// The sudoku gates: a 1..N range check on every cell, the binding of every
+/// given to the grid, and one-of-each checks on rows, columns and boxes. `N`
+/// defaults to the classic 9, with 3x3 boxes; boxes are `BOX_ROWS` tall and
+/// `BOX_COLS` wide (square unless given), and `BOX_ROWS * BOX_COLS` must
+/// equal `N`.
+#[derive(Debug, Clone)]
+pub struct SudokuChip<
+    F: FieldExt,
+    const N: usize = 9,
+    const BOX_ROWS: usize = 3,
+    const BOX_COLS: usize = BOX_ROWS,
+> {
+    config: SudokuConfig<N, BOX_ROWS, BOX_COLS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const N: usize, const BOX_ROWS: usize, const BOX_COLS: usize>
+    SudokuChip<F, N, BOX_ROWS, BOX_COLS>
+{
+    pub fn construct(config: SudokuConfig<N, BOX_ROWS, BOX_COLS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Allocates the grid and adds the sudoku gates, checking units with
+    /// [`UniquenessMode::Permutation`].
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> SudokuConfig<N, BOX_ROWS, BOX_COLS> {
+        Self::configure_with_uniqueness(meta, UniquenessMode::Permutation)
+    }
+
+    /// Like [`Self::configure`], but checks rows, columns and boxes with the
+    /// given [`UniquenessMode`].
+    pub fn configure_with_uniqueness(
+        meta: &mut ConstraintSystem<F>,
+        uniqueness: UniquenessMode,
+    ) -> SudokuConfig<N, BOX_ROWS, BOX_COLS> {
+        Self::configure_with(meta, uniqueness, Layout::Wide, &Self::classic_alphabet())
+    }
+
+    /// Like [`Self::configure`], but stores the grid as `layout` says, e.g.
+    /// in one advice column of `N * N` rows with `Layout::Packed { columns:
+    /// 1 }`. Under a packed layout the columns gate is enabled once per
+    /// grid like the others, instead of on every row.
+    pub fn configure_with_layout(
+        meta: &mut ConstraintSystem<F>,
+        layout: Layout,
+    ) -> SudokuConfig<N, BOX_ROWS, BOX_COLS> {
+        Self::configure_with(
+            meta,
+            UniquenessMode::Permutation,
+            layout,
+            &Self::classic_alphabet(),
+        )
+    }
+
+    /// Like [`Self::configure`], but over the `N` distinct, nonzero digits
+    /// of `alphabet` instead of `1..=N`, for teaching boards that use other
+    /// digits: the range check looks cells up in the alphabet and units
+    /// compare power sums against the alphabet's.
+    pub fn configure_alphabet(
+        meta: &mut ConstraintSystem<F>,
+        alphabet: &[u64],
+    ) -> SudokuConfig<N, BOX_ROWS, BOX_COLS> {
+        assert_eq!(
+            alphabet.len(),
+            N,
+            "the alphabet must have one digit per cell of a unit"
+        );
+        assert!(
+            !alphabet.contains(&0),
+            "zero marks blanks and cannot be a digit"
+        );
+        assert!(
+            alphabet.iter().collect::<BTreeSet<_>>().len() == N,
+            "alphabet digits must be distinct"
+        );
+        Self::configure_with(meta, UniquenessMode::Permutation, Layout::Wide, alphabet)
+    }
+
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "configure", skip_all, fields(n = N)))]
+    fn configure_with(
+        meta: &mut ConstraintSystem<F>,
+        uniqueness: UniquenessMode,
+        layout: Layout,
+        alphabet: &[u64],
+    ) -> SudokuConfig<N, BOX_ROWS, BOX_COLS> {
+        assert_eq!(BOX_ROWS * BOX_COLS, N, "boxes must tile the board");
+        if let Layout::Packed { columns } = layout {
+            assert!(
+                columns > 0 && N % columns == 0,
+                "packed columns must divide the board size"
+            );
+        }
+
+        // `always_enabled` also gates the range check lookups, which simple
+        // selectors cannot appear in.
+        let always_enabled = meta.complex_selector();
+        let only_first_enabled = meta.selector();
+        let advice: Vec<_> = (0..layout.columns(N))
+            .map(|_| meta.advice_column())
+            .collect();
+        let instance = meta.instance_column();
+        let givens: Vec<_> = (0..layout.columns(N))
+            .map(|_| meta.advice_column())
+            .collect();
+
+        for &adv in &advice {
+            meta.enable_equality(adv);
+        }
+        meta.enable_equality(instance);
+        for &given in &givens {
+            meta.enable_equality(given);
+        }
+        // the column and rotation of grid cell `(row, col)`
+        let place = |cell: Cell| {
+            let (column, rotation) = layout.place(N, cell);
+            (column, Rotation(rotation as i32))
+        };
+
+        // Grid cell (row, col) sits in advice[row] at rotation col: a grid row
+        // runs down one advice column, and a grid column across one region row.
+        //
+        //   advice[0]  |   ...   |  advice[8]  | always_enabled | only_first_enabled
+        //       5      |         |      7      |       1        |         1
+        //       7      |         |      1      |       1        |         0
+        //       1      |         |      2      |       1        |         0
+        //       6      |         |      9      |       1        |         0
+        //       2      |         |      3      |       1        |         0
+        //       4      |         |      6      |       1        |         0
+        //       3      |         |      4      |       1        |         0
+        //       9      |         |      8      |       1        |         0
+        //       8      |         |      5      |       1        |         0
+        //
+        // A packed layout instead runs the cells row-major down its columns,
+        // and only the range check is enabled on every row.
+
+        let digits = Self::configure_range_check(meta, always_enabled, &advice, alphabet);
+
+        // Bind every given, not only the ones the prover chose to copy from the
+        // instance: where the public cell c (copied into `givens`) is nonzero,
+        // the grid cell v must equal it, i.e. c * (c - v) == 0.
+        meta.create_gate("givens", |meta| {
+            let only_first_enabled = meta.query_selector(only_first_enabled);
+
+            let mut constraints = Vec::new();
+
+            for row in 0..N {
+                for col in 0..N {
+                    let (column, rotation) = place((row, col));
+                    let given = meta.query_advice(givens[column], rotation);
+                    let element = meta.query_advice(advice[column], rotation);
+
+                    constraints
+                        .push(only_first_enabled.clone() * given.clone() * (given - element));
+                }
+            }
+
+            constraints
+        });
+
+        let [rows, columns, boxes] = match uniqueness {
+            UniquenessMode::Permutation => ["rows", "columns", "boxes"],
+            UniquenessMode::SumProduct => [
+                "rows (sum/product)",
+                "columns (sum/product)",
+                "boxes (sum/product)",
+            ],
+        };
+
+        match layout {
+            // Enabled on every region row, each of which holds one grid column.
+            Layout::Wide => meta.create_gate(columns, |meta| {
+                let always_enabled = meta.query_selector(always_enabled);
+
+                let cells: Vec<_> = advice
+                    .iter()
+                    .map(|&column| meta.query_advice(column, Rotation::cur()))
+                    .collect();
+
+                Self::unit_constraints(uniqueness, alphabet, &cells)
+                    .into_iter()
+                    .map(|constraint| always_enabled.clone() * constraint)
+                    .collect::<Vec<_>>()
+            }),
+            Layout::Packed { .. } => meta.create_gate(columns, |meta| {
+                let only_first_enabled = meta.query_selector(only_first_enabled);
+
+                let mut constraints = Vec::new();
+
+                for col in 0..N {
+                    let cells: Vec<_> = (0..N)
+                        .map(|row| {
+                            let (column, rotation) = place((row, col));
+                            meta.query_advice(advice[column], rotation)
+                        })
+                        .collect();
+
+                    constraints.extend(
+                        Self::unit_constraints(uniqueness, alphabet, &cells)
+                            .into_iter()
+                            .map(|constraint| only_first_enabled.clone() * constraint),
+                    );
+                }
+
+                constraints
+            }),
+        }
+
+        // Enabled once; under the wide layout each advice column holds one
+        // grid row.
+        meta.create_gate(rows, |meta| {
+            let only_first_enabled = meta.query_selector(only_first_enabled);
+
+            let mut constraints = Vec::new();
+
+            for row in 0..N {
+                let cells: Vec<_> = (0..N)
+                    .map(|col| {
+                        let (column, rotation) = place((row, col));
+                        meta.query_advice(advice[column], rotation)
+                    })
+                    .collect();
+
+                constraints.extend(
+                    Self::unit_constraints(uniqueness, alphabet, &cells)
+                        .into_iter()
+                        .map(|constraint| only_first_enabled.clone() * constraint),
+                );
+            }
+
+            constraints
+        });
+
+        meta.create_gate(boxes, |meta| {
+            let only_first_enabled = meta.query_selector(only_first_enabled);
+
+            let mut constraints = Vec::new();
+
+            for i in 0..N / BOX_ROWS {
+                for j in 0..N / BOX_COLS {
+                    let mut cells = Vec::with_capacity(N);
+                    for k in 0..BOX_ROWS {
+                        for l in 0..BOX_COLS {
+                            let (column, rotation) = place((i * BOX_ROWS + k, j * BOX_COLS + l));
+                            cells.push(meta.query_advice(advice[column], rotation));
+                        }
+                    }
+
+                    constraints.extend(
+                        Self::unit_constraints(uniqueness, alphabet, &cells)
+                            .into_iter()
+                            .map(|constraint| only_first_enabled.clone() * constraint),
+                    );
+                }
+            }
+
+            constraints
+        });
+
+        SudokuConfig {
+            always_enabled,
+            only_first_enabled,
+            advice,
+            instance,
+            givens,
+            digits,
+            alphabet: alphabet.to_vec(),
+            versions: BTreeSet::from([CircuitVersion::Classic]),
+            uniqueness,
+            layout,
+            inequalities: Vec::new(),
+            inverses: Vec::new(),
+        }
+    }
+
+    /// `1^power + 2^power + ... + N^power`, what a unit's cells raised to
+    /// `power` must sum to. Computed in the field, since `16^16` already
+    /// overflows a u64.
+    pub fn power_sum(power: u32) -> F {
+        Self::alphabet_power_sum(&Self::classic_alphabet(), power)
+    }
+
+    fn alphabet_power_sum(alphabet: &[u64], power: u32) -> F {
+        alphabet
+            .iter()
+            .map(|&digit| F::from(digit))
+            .fold(F::zero(), |acc, digit| {
+                acc + (1..power).fold(digit, |term, _| term * digit)
+            })
+    }
+
+    /// `1 + 2 + ... + N`, 45 on the classic board.
+    pub fn digit_sum() -> F {
+        Self::power_sum(1)
+    }
+
+    /// `1 * 2 * ... * N`, 9! = 362880 on the classic board.
+    pub fn digit_product() -> F {
+        Self::alphabet_product(&Self::classic_alphabet())
+    }
+
+    fn alphabet_product(alphabet: &[u64]) -> F {
+        alphabet
+            .iter()
+            .map(|&digit| F::from(digit))
+            .fold(F::one(), |acc, digit| acc * digit)
+    }
+
+    /// The digits `1..=N` of the classic board.
+    pub(crate) fn classic_alphabet() -> Vec<u64> {
+        (1..=N as u64).collect()
+    }
+
+    /// Constraints that the `N` `cells`, each already range checked into
+    /// `alphabet`, hold every digit once, under the given [`UniquenessMode`].
+    /// Every unit gate (rows, columns, boxes and the variants' extra units)
+    /// goes through here, so the distinctness argument lives in one place.
+    fn unit_constraints(
+        uniqueness: UniquenessMode,
+        alphabet: &[u64],
+        cells: &[Expression<F>],
+    ) -> Vec<Expression<F>> {
+        match uniqueness {
+            UniquenessMode::SumProduct => {
+                let product = cells
+                    .iter()
+                    .fold(Expression::Constant(F::from(1)), |expr, cell| {
+                        expr * cell.clone()
+                    });
+                let sum = cells
+                    .iter()
+                    .fold(Expression::Constant(F::from(0)), |expr, cell| {
+                        expr + cell.clone()
+                    });
+
+                vec![
+                    product - Expression::Constant(Self::alphabet_product(alphabet)),
+                    sum - Expression::Constant(Self::alphabet_power_sum(alphabet, 1)),
+                ]
+            }
+            UniquenessMode::Permutation => (1..=N as u32)
+                .map(|power| {
+                    let sum = cells
+                        .iter()
+                        .fold(Expression::Constant(F::from(0)), |expr, cell| {
+                            let term = (1..power).fold(cell.clone(), |term, _| term * cell.clone());
+                            expr + term
+                        });
+
+                    sum - Expression::Constant(Self::alphabet_power_sum(alphabet, power))
+                })
+                .collect(),
+        }
+    }
+
+    /// Adds the range check: on every row where `selector` is enabled, each
+    /// of the `advice` cells must be found in a table of the digits of
+    /// `alphabet`, which is returned for [`Self::load_digits`] to fill. One
+    /// lookup per column replaces the degree-`N` vanishing product this used
+    /// to be.
+    fn configure_range_check(
+        meta: &mut ConstraintSystem<F>,
+        selector: Selector,
+        advice: &[Column<Advice>],
+        alphabet: &[u64],
+    ) -> TableColumn {
+        let digits = meta.lookup_table_column();
+        let first = F::from(alphabet[0]);
+
+        for &column in advice {
+            meta.lookup(|meta| {
+                let selector = meta.query_selector(selector);
+                let value = meta.query_advice(column, Rotation::cur());
+                let first = Expression::Constant(first);
+
+                // Rows with the selector off look up the alphabet's first
+                // digit, which is always in the table; 0 is not, so blanks
+                // left in the grid are rejected.
+                vec![(selector * (value - first.clone()) + first, digits)]
+            });
+        }
+
+        digits
+    }
+
+    /// Fills the table allocated by [`Self::configure_range_check`] with
+    /// `alphabet`.
+    fn load_digits(
+        layouter: &mut impl Layouter<F>,
+        digits: TableColumn,
+        alphabet: &[u64],
+    ) -> Result<(), Error> {
+        layouter.assign_table(
+            || "digits",
+            |mut table| {
+                for (offset, &digit) in alphabet.iter().enumerate() {
+                    table.assign_cell(
+                        || format!("digit {}", digit),
+                        digits,
+                        offset,
+                        || Value::known(F::from(digit)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Queries the advice cell holding grid position `(row, col)`, relative
+    /// to the first row of the sudoku region.
+    fn query_cell(
+        meta: &mut VirtualCells<'_, F>,
+        config: &SudokuConfig<N, BOX_ROWS, BOX_COLS>,
+        (row, col): Cell,
+    ) -> Expression<F> {
+        let (column, rotation) = config.position((row, col));
+        meta.query_advice(column, Rotation(rotation as i32))
+    }
+
+    /// Adds kropki dot constraints: every `white` pair must hold consecutive
+    /// digits (`|a - b| == 1`) and every `black` pair digits in a 2:1 ratio
+    /// (`a == 2b || b == 2a`).
+    pub fn configure_kropki(
+        meta: &mut ConstraintSystem<F>,
+        config: &mut SudokuConfig<N, BOX_ROWS, BOX_COLS>,
+        white: &[(Cell, Cell)],
+        black: &[(Cell, Cell)],
+    ) {
+        // halo2 rejects gates without constraints, so skip empty dot lists.
+        if !white.is_empty() {
+            meta.create_gate("kropki white", |meta| {
+                let only_first_enabled = meta.query_selector(config.only_first_enabled);
+
+                white
+                    .iter()
+                    .map(|&(a, b)| {
+                        let diff =
+                            Self::query_cell(meta, config, a) - Self::query_cell(meta, config, b);
+                        let one = Expression::Constant(F::one());
+
+                        only_first_enabled.clone() * (diff.clone() - one.clone()) * (diff + one)
+                    })
+                    .collect::<Vec<_>>()
+            });
+            config.versions.insert(CircuitVersion::Kropki);
+        }
+
+        if !black.is_empty() {
+            meta.create_gate("kropki black", |meta| {
+                let only_first_enabled = meta.query_selector(config.only_first_enabled);
+
+                black
+                    .iter()
+                    .map(|&(a, b)| {
+                        let a = Self::query_cell(meta, config, a);
+                        let b = Self::query_cell(meta, config, b);
+                        let two = Expression::Constant(F::from(2));
+
+                        only_first_enabled.clone()
+                            * (a.clone() - two.clone() * b.clone())
+                            * (b - two * a)
+                    })
+                    .collect::<Vec<_>>()
+            });
+            config.versions.insert(CircuitVersion::Kropki);
+        }
+    }
+
+    /// Returns `(v - d_1) * (v - d_2) * ...` over `digits`, which vanishes
+    /// exactly when `value` is one of them.
+    fn one_of(value: Expression<F>, digits: impl IntoIterator<Item = u64>) -> Expression<F> {
+        digits
+            .into_iter()
+            .fold(Expression::Constant(F::one()), |expr, digit| {
+                expr * (value.clone() - Expression::Constant(F::from(digit)))
+            })
+    }
+
+    /// Adds even/odd constraints: every `even` cell must hold an even digit
+    /// of the config's alphabet and every `odd` cell an odd one. Each is the
+    /// vanishing product over the allowed digits, which is equivalent to
+    /// requiring the cell's one-hot digit indicators to sum to one over even
+    /// (or odd) values, without witnessing the indicators.
+    pub fn configure_parity(
+        meta: &mut ConstraintSystem<F>,
+        config: &mut SudokuConfig<N, BOX_ROWS, BOX_COLS>,
+        even: &[Cell],
+        odd: &[Cell],
+    ) {
+        let (even_digits, odd_digits): (Vec<u64>, Vec<u64>) = config
+            .alphabet
+            .iter()
+            .copied()
+            .partition(|&digit| digit % 2 == 0);
+
+        if !even.is_empty() || !odd.is_empty() {
+            meta.create_gate("parity", |meta| {
+                let only_first_enabled = meta.query_selector(config.only_first_enabled);
+
+                let even = even.iter().map(|&cell| (cell, &even_digits));
+                let odd = odd.iter().map(|&cell| (cell, &odd_digits));
+
+                even.chain(odd)
+                    .map(|(cell, digits)| {
+                        let value = Self::query_cell(meta, config, cell);
+                        only_first_enabled.clone() * Self::one_of(value, digits.iter().copied())
+                    })
+                    .collect::<Vec<_>>()
+            });
+            config.versions.insert(CircuitVersion::Parity);
+        }
+    }
+
+    /// Adds pencil marks: every listed cell must hold one of its candidates,
+    /// the vanishing product over them as for [`Self::configure_parity`].
+    /// Cells are constrained in row-major order; a cell with no candidates
+    /// can never be satisfied.
+    pub fn configure_candidates(
+        meta: &mut ConstraintSystem<F>,
+        config: &mut SudokuConfig<N, BOX_ROWS, BOX_COLS>,
+        candidates: &HashMap<Cell, Vec<u8>>,
+    ) {
+        if !candidates.is_empty() {
+            let mut cells: Vec<_> = candidates.iter().collect();
+            cells.sort_unstable_by_key(|&(&cell, _)| cell);
+
+            meta.create_gate("candidates", |meta| {
+                let only_first_enabled = meta.query_selector(config.only_first_enabled);
+
+                cells
+                    .into_iter()
+                    .map(|(&cell, digits)| {
+                        let value = Self::query_cell(meta, config, cell);
+                        let digits = digits.iter().map(|&digit| u64::from(digit));
+                        only_first_enabled.clone() * Self::one_of(value, digits)
+                    })
+                    .collect::<Vec<_>>()
+            });
+            config.versions.insert(CircuitVersion::Candidates);
+        }
+    }
+
+    /// Adds quadruple clues: every listed digit must appear in at least one of
+    /// the clue's four cells, i.e. the product of `(cell - digit)` over the
+    /// four cells vanishes. As with [`Self::configure_parity`] this stands in
+    /// for one-hot indicators summing to at least one, without witnessing
+    /// them. A digit listed twice is only required once.
+    pub fn configure_quadruples(
+        meta: &mut ConstraintSystem<F>,
+        config: &mut SudokuConfig<N, BOX_ROWS, BOX_COLS>,
+        quads: &[(Cell4, Vec<u64>)],
+    ) {
+        if quads.iter().any(|(_, digits)| !digits.is_empty()) {
+            meta.create_gate("quadruples", |meta| {
+                let only_first_enabled = meta.query_selector(config.only_first_enabled);
+
+                let mut constraints = Vec::new();
+
+                for (cells, digits) in quads {
+                    let values: Vec<_> = cells
+                        .iter()
+                        .map(|&cell| Self::query_cell(meta, config, cell))
+                        .collect();
+
+                    for &digit in digits {
+                        let missing =
+                            values
+                                .iter()
+                                .fold(Expression::Constant(F::one()), |expr, value| {
+                                    expr * (value.clone() - Expression::Constant(F::from(digit)))
+                                });
+                        constraints.push(only_first_enabled.clone() * missing);
+                    }
+                }
+
+                constraints
+            });
+            config.versions.insert(CircuitVersion::Quadruple);
+        }
+    }
+
+    /// Adds the X-Sudoku rule: the cells `(i, i)` and the cells
+    /// `(i, N - 1 - i)` must each hold every digit once, checked with the
+    /// config's [`UniquenessMode`] like rows, columns and boxes.
+    pub fn configure_diagonals(
+        meta: &mut ConstraintSystem<F>,
+        config: &mut SudokuConfig<N, BOX_ROWS, BOX_COLS>,
+    ) {
+        let diagonals: [(&'static str, fn(usize) -> Cell); 2] = [
+            ("diagonal", |i| (i, i)),
+            ("anti-diagonal", |i| (i, N - 1 - i)),
+        ];
+
+        for (name, cell) in diagonals {
+            meta.create_gate(name, |meta| {
+                let only_first_enabled = meta.query_selector(config.only_first_enabled);
+
+                let cells: Vec<_> = (0..N)
+                    .map(|i| Self::query_cell(meta, config, cell(i)))
+                    .collect();
+
+                Self::unit_constraints(config.uniqueness, &config.alphabet, &cells)
+                    .into_iter()
+                    .map(|constraint| only_first_enabled.clone() * constraint)
+                    .collect::<Vec<_>>()
+            });
+        }
+
+        config.versions.insert(CircuitVersion::Diagonal);
+    }
+
+    /// Adds killer cages: the cells of every cage, which may lie anywhere on
+    /// the grid, must sum to its target. Constraint `i` of the "cages" gate is
+    /// cage `i`. Cells are not checked for repeats within a cage, so a cage
+    /// that does not sit inside one row, column or box can hold a digit twice.
+    pub fn configure_cages(
+        meta: &mut ConstraintSystem<F>,
+        config: &mut SudokuConfig<N, BOX_ROWS, BOX_COLS>,
+        cages: &[(u64, Vec<Cell>)],
+    ) {
+        if !cages.is_empty() {
+            meta.create_gate("cages", |meta| {
+                let only_first_enabled = meta.query_selector(config.only_first_enabled);
+
+                cages
+                    .iter()
+                    .map(|(target, cells)| {
+                        let sum = cells
+                            .iter()
+                            .fold(Expression::Constant(F::zero()), |expr, &cell| {
+                                expr + Self::query_cell(meta, config, cell)
+                            });
+
+                        only_first_enabled.clone() * (sum - Expression::Constant(F::from(*target)))
+                    })
+                    .collect::<Vec<_>>()
+            });
+            config.versions.insert(CircuitVersion::Killer);
+        }
+    }
+
+    /// Adds the anti-knight rule if `anti_knight` is set and the anti-king
+    /// rule if `anti_king` is: two cells a knight's (or king's) move apart
+    /// must hold different digits. Each pair `(a, b)` gets a witnessed
+    /// inverse `w` of `a - b` in new advice columns and the constraint
+    /// `(a - b) * w == 1`, which no `w` satisfies when `a == b`. Pairs in
+    /// one box, and a king's orthogonal steps, are already distinct and are
+    /// skipped. The inverses are filled by [`Self::assign`],
+    /// [`Self::assign_batch`] and [`Self::assign_full`], not by
+    /// [`Self::assign_chunked`].
+    pub fn configure_anti_moves(
+        meta: &mut ConstraintSystem<F>,
+        config: &mut SudokuConfig<N, BOX_ROWS, BOX_COLS>,
+        anti_knight: bool,
+        anti_king: bool,
+    ) {
+        // only moves downwards, so every pair is listed once
+        let mut moves = Vec::new();
+        if anti_knight {
+            moves.extend([(1, -2), (1, 2), (2, -1), (2, 1)]);
+        }
+        if anti_king {
+            moves.extend([(1, -1), (1, 1)]);
+        }
+
+        let mut pairs = Vec::new();
+        for row in 0..N {
+            for col in 0..N {
+                for &(down, across) in &moves {
+                    let other = (row + down, col as i64 + across);
+                    if other.0 >= N || other.1 < 0 || other.1 >= N as i64 {
+                        continue;
+                    }
+                    let other = (other.0, other.1 as usize);
+                    let same_box = row / BOX_ROWS == other.0 / BOX_ROWS
+                        && col / BOX_COLS == other.1 / BOX_COLS;
+                    if !same_box {
+                        pairs.push(((row, col), other));
+                    }
+                }
+            }
+        }
+
+        if !pairs.is_empty() {
+            let inverses: Vec<_> = (0..pairs.len().div_ceil(N))
+                .map(|_| meta.advice_column())
+                .collect();
+
+            meta.create_gate("anti-moves", |meta| {
+                let only_first_enabled = meta.query_selector(config.only_first_enabled);
+
+                pairs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &(a, b))| {
+                        let a = Self::query_cell(meta, config, a);
+                        let b = Self::query_cell(meta, config, b);
+                        let inverse = meta.query_advice(inverses[i / N], Rotation((i % N) as i32));
+
+                        only_first_enabled.clone() * NotEqualConfig::constraint(a, b, inverse)
+                    })
+                    .collect::<Vec<_>>()
+            });
+
+            config.inequalities = pairs;
+            config.inverses = inverses;
+            config.versions.insert(CircuitVersion::AntiMove);
+        }
+    }
+
+    /// Reveals the selected aggregates through a new instance column, `N`
+    /// cells per aggregate in the order given, with a gate binding each cell
+    /// to the witness. Returns the column so callers know where it sits in
+    /// the public input.
+    pub fn configure_aggregates(
+        meta: &mut ConstraintSystem<F>,
+        config: &SudokuConfig<N, BOX_ROWS, BOX_COLS>,
+        aggregates: &[LineAggregate],
+    ) -> Column<Instance> {
+        let output = meta.instance_column();
+
+        meta.create_gate("aggregates", |meta| {
+            let only_first_enabled = meta.query_selector(config.only_first_enabled);
+
+            let mut constraints = Vec::new();
+
+            for (a, aggregate) in aggregates.iter().enumerate() {
+                for line in 0..N {
+                    let sum = (0..N).fold(Expression::Constant(F::zero()), |expr, i| {
+                        let cell = match aggregate {
+                            LineAggregate::RowSums => (line, i),
+                            LineAggregate::ColumnSums => (i, line),
+                        };
+                        expr + Self::query_cell(meta, config, cell)
+                    });
+                    let revealed = meta.query_instance(output, Rotation((a * N + line) as i32));
+
+                    constraints.push(only_first_enabled.clone() * (sum - revealed));
+                }
+            }
+
+            constraints
+        });
+
+        output
+    }
+
+    /// Assigns the grid and returns its cells indexed `[row][col]`: copied
+    /// from the instance where `solution` is zero, witnessed otherwise.
+    pub fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        solution: &Vec<Vec<F>>,
+    ) -> Result<Vec<Vec<AssignedCell<F, F>>>, Error> {
+        let mut grids = self.assign_batch(layouter, std::slice::from_ref(solution))?;
+        Ok(grids.remove(0))
+    }
+
+    /// Like [`Self::assign`], for several grids stacked in one region: grid
+    /// `p` takes the `p`-th block of [`LayoutInfo::rows_used`] region rows
+    /// (`N` under the wide layout), with the gates enabled again at the top
+    /// of each block, and reads its puzzle from instance rows `p * N * N..(p
+    /// + 1) * N * N`. Returns the grids in order.
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(name = "assign", skip_all, fields(n = N, grids = solutions.len()))
+    )]
+    pub fn assign_batch(
+        &self,
+        mut layouter: impl Layouter<F>,
+        solutions: &[Vec<Vec<F>>],
+    ) -> Result<Vec<Vec<Vec<AssignedCell<F, F>>>>, Error> {
+        Self::load_digits(&mut layouter, self.config.digits, &self.config.alphabet)?;
+        let rows = self.config.layout.rows(N);
+
+        layouter.assign_region(
+            || "entire table",
+            |mut region| {
+                solutions
+                    .iter()
+                    .enumerate()
+                    .map(|(p, solution)| {
+                        let entries = Entry::from_zero_blanks(solution);
+                        self.assign_block(&mut region, p * rows, p * N * N, &entries)
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    /// Like [`Self::assign`], but into a `region` shared with other gadgets:
+    /// the grid takes [`LayoutInfo::rows_used`] region rows from `offset`
+    /// (`N` under the wide layout), with every selector
+    /// enabled relative to `offset`, and reads its puzzle from instance rows
+    /// `0..N * N`. Load the range-check table once with
+    /// [`Self::load_table`].
+    pub fn assign_at(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        solution: &[Vec<F>],
+    ) -> Result<Vec<Vec<AssignedCell<F, F>>>, Error> {
+        self.assign_block(region, offset, 0, &Entry::from_zero_blanks(solution))
+    }
+
+    /// Like [`Self::assign`], but each cell says whether it is a clue, so a
+    /// [`Entry::Filled`] zero is witnessed and rejected by the range check
+    /// instead of silently taking the clue's value from the instance.
+    pub fn assign_entries(
+        &self,
+        mut layouter: impl Layouter<F>,
+        entries: &[Vec<Entry<F>>],
+    ) -> Result<Vec<Vec<AssignedCell<F, F>>>, Error> {
+        Self::load_digits(&mut layouter, self.config.digits, &self.config.alphabet)?;
+        layouter.assign_region(
+            || "entire table",
+            |mut region| self.assign_block(&mut region, 0, 0, entries),
+        )
+    }
+
+    /// Fills the range-check table, once per circuit, for
+    /// [`Self::assign_at`]. The other `assign` methods load it themselves.
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        Self::load_digits(layouter, self.config.digits, &self.config.alphabet)
+    }
+
+    /// Assigns one grid at region row `top`, reading its puzzle from instance
+    /// row `first` on, and enables the gates over its block.
+    fn assign_block(
+        &self,
+        region: &mut Region<'_, F>,
+        top: usize,
+        first: usize,
+        entries: &[Vec<Entry<F>>],
+    ) -> Result<Vec<Vec<AssignedCell<F, F>>>, Error> {
+        self.config.only_first_enabled.enable(region, top)?; // enable only first row
+        for row in 0..self.config.layout.rows(N) {
+            self.config.always_enabled.enable(region, top + row)?; // enable the whole column
+        }
+
+        let mut grid = Vec::with_capacity(N);
+        for row in 0..N {
+            let mut cells = Vec::with_capacity(N);
+            for col in 0..N {
+                let (column, offset) = self.config.position((row, col));
+                let cell = match entries[row][col] {
+                    Entry::Given => region.assign_advice_from_instance(
+                        || format!("copy row {} col {} from instance to advice", row, col),
+                        self.config.instance,
+                        first + row * N + col, // row in instance column
+                        column,
+                        top + offset, // row in advice column
+                    )?,
+                    Entry::Filled(value) => region.assign_advice(
+                        || format!("copy row {} col {} from solution to advice", row, col),
+                        column,
+                        top + offset, // row in solution column
+                        || Value::known(value),
+                    )?,
+                };
+                cells.push(cell);
+            }
+            grid.push(cells);
+        }
+
+        // copy every instance cell for the "givens" gate
+        for row in 0..N {
+            for col in 0..N {
+                let (column, offset) = self.config.given_position((row, col));
+                region.assign_advice_from_instance(
+                    || format!("copy given row {} col {}", row, col),
+                    self.config.instance,
+                    first + row * N + col,
+                    column,
+                    top + offset,
+                )?;
+            }
+        }
+
+        self.assign_inverses(region, top, &grid)?;
+        Ok(grid)
+    }
+
+    /// Witnesses the inverse of each [`Self::configure_anti_moves`] pair's
+    /// difference in the block starting at region row `top`. Equal cells get
+    /// zero, which the gate then rejects.
+    fn assign_inverses(
+        &self,
+        region: &mut Region<'_, F>,
+        top: usize,
+        grid: &[Vec<AssignedCell<F, F>>],
+    ) -> Result<(), Error> {
+        for (i, &((ra, ca), (rb, cb))) in self.config.inequalities.iter().enumerate() {
+            let inverse = NotEqualConfig::inverse(
+                grid[ra][ca].value().copied(),
+                grid[rb][cb].value().copied(),
+            );
+            region.assign_advice(
+                || format!("inverse of ({},{}) - ({},{})", ra, ca, rb, cb),
+                self.config.inverses[i / N],
+                top + i % N,
+                || inverse,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Like [`Self::assign_with_clues`], and additionally ties each cell
+    /// `clues[row][col]` marks to its instance cell by a copy constraint.
+    pub fn assign_full(
+        &self,
+        layouter: impl Layouter<F>,
+        grid: &[Vec<F>],
+        clues: &[Vec<bool>],
+    ) -> Result<Vec<Vec<AssignedCell<F, F>>>, Error> {
+        self.assign_complete(layouter, grid, |row, col| clues[row][col])
+    }
+
+    /// Proves that `full_solution`, the complete solved grid, is a valid
+    /// completion of the puzzle in the instance. Every cell is witnessed
+    /// from `full_solution` and range and unit checked like any other grid,
+    /// and every instance cell is copied next to it for the "givens" gate,
+    /// which forces each grid cell under a nonzero clue to equal it; a
+    /// completion that overwrites a clue fails. The binding depends on the
+    /// instance alone, and unlike [`Self::assign`] the solution never uses
+    /// zero to mean "copy from the instance".
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "assign", skip_all, fields(n = N)))]
+    pub fn assign_with_clues(
+        &self,
+        layouter: impl Layouter<F>,
+        full_solution: &[Vec<F>],
+    ) -> Result<Vec<Vec<AssignedCell<F, F>>>, Error> {
+        self.assign_complete(layouter, full_solution, |_, _| false)
+    }
+
+    /// Witnesses the complete grid `full_solution` and the instance copy for
+    /// the "givens" gate, and ties the cells `copied` picks to their instance
+    /// cells.
+    fn assign_complete(
+        &self,
+        mut layouter: impl Layouter<F>,
+        full_solution: &[Vec<F>],
+        copied: impl Fn(usize, usize) -> bool,
+    ) -> Result<Vec<Vec<AssignedCell<F, F>>>, Error> {
+        Self::load_digits(&mut layouter, self.config.digits, &self.config.alphabet)?;
+
+        layouter.assign_region(
+            || "entire table",
+            |mut region| {
+                self.config.only_first_enabled.enable(&mut region, 0)?; // enable only first row
+                for row in 0..self.config.layout.rows(N) {
+                    self.config.always_enabled.enable(&mut region, row)?; // enable the whole column
+                }
+
+                let mut cells = Vec::with_capacity(N);
+                for row in 0..N {
+                    let mut line = Vec::with_capacity(N);
+                    for col in 0..N {
+                        let (column, offset) = self.config.position((row, col));
+                        let cell = region.assign_advice(
+                            || format!("copy row {} col {} from grid to advice", row, col),
+                            column,
+                            offset,
+                            || Value::known(full_solution[row][col]),
+                        )?;
+                        let (column, offset) = self.config.given_position((row, col));
+                        let given = region.assign_advice_from_instance(
+                            || format!("copy given row {} col {}", row, col),
+                            self.config.instance,
+                            row * N + col,
+                            column,
+                            offset,
+                        )?;
+                        if copied(row, col) {
+                            region.constrain_equal(cell.cell(), given.cell())?;
+                        }
+                        line.push(cell);
+                    }
+                    cells.push(line);
+                }
+
+                self.assign_inverses(&mut region, 0, &cells)?;
+                Ok(cells)
+            },
+        )
+    }
+
+    /// Assigns the same cells as [`Self::assign`], but witnesses the grid one
+    /// row per region and only then copies the cells, via copy constraints,
+    /// into the region where the gates are enabled. Each chunk region touches
+    /// a single grid row, which keeps per-region synthesis work small for
+    /// larger variants; halo2 still allocates whole columns, so the overall
+    /// assignment matrix does not shrink.
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "assign", skip_all, fields(n = N)))]
+    pub fn assign_chunked(
+        &self,
+        mut layouter: impl Layouter<F>,
+        solution: &Vec<Vec<F>>,
+    ) -> Result<Vec<Vec<AssignedCell<F, F>>>, Error> {
+        Self::load_digits(&mut layouter, self.config.digits, &self.config.alphabet)?;
+
+        let mut chunks = Vec::with_capacity(N);
+        for row in 0..N {
+            let chunk = layouter.assign_region(
+                || format!("grid row {}", row),
+                |mut region| {
+                    let mut cells = Vec::with_capacity(N);
+                    let mut givens = Vec::with_capacity(N);
+                    for col in 0..N {
+                        let (column, offset) = self.config.position((row, col));
+                        let cell = if solution[row][col] == F::zero() {
+                            region.assign_advice_from_instance(
+                                || format!("copy row {} col {} from instance to advice", row, col),
+                                self.config.instance,
+                                row * N + col,
+                                column,
+                                offset,
+                            )?
+                        } else {
+                            region.assign_advice(
+                                || format!("copy row {} col {} from solution to advice", row, col),
+                                column,
+                                offset,
+                                || Value::known(solution[row][col]),
+                            )?
+                        };
+                        cells.push(cell);
+
+                        let (column, offset) = self.config.given_position((row, col));
+                        givens.push(region.assign_advice_from_instance(
+                            || format!("copy given row {} col {}", row, col),
+                            self.config.instance,
+                            row * N + col,
+                            column,
+                            offset,
+                        )?);
+                    }
+                    Ok((cells, givens))
+                },
+            )?;
+            chunks.push(chunk);
+        }
+
+        layouter.assign_region(
+            || "entire table",
+            |mut region| {
+                self.config.only_first_enabled.enable(&mut region, 0)?;
+                for row in 0..self.config.layout.rows(N) {
+                    self.config.always_enabled.enable(&mut region, row)?;
+                }
+
+                let mut grid = Vec::with_capacity(N);
+                for (row, (cells, givens)) in chunks.iter().enumerate() {
+                    let mut copied = Vec::with_capacity(N);
+                    for (col, (cell, given)) in cells.iter().zip(givens).enumerate() {
+                        let (column, offset) = self.config.position((row, col));
+                        copied.push(cell.copy_advice(
+                            || format!("copy row {} col {} from chunk", row, col),
+                            &mut region,
+                            column,
+                            offset,
+                        )?);
+                        let (column, offset) = self.config.given_position((row, col));
+                        given.copy_advice(
+                            || format!("copy given row {} col {} from chunk", row, col),
+                            &mut region,
+                            column,
+                            offset,
+                        )?;
+                    }
+                    grid.push(copied);
+                }
+                Ok(grid)
+            },
+        )
+    }
+}
+
+/// Proves knowledge of a solution to the puzzle supplied as the public
+/// input: a single instance column holding the `N * N` cells row-major
+/// (see [`packed_instance`](crate::io::packed_instance)), zero at every
+/// blank. The 81 instance rows of a 9x9 board need `k >= 7`. The parameters
+/// default to the classic 9x9 board; other sizes are built with
+/// [`MyCircuit::sized`].
+///
+/// ```
+/// use halo2_proofs::{dev::MockProver, pasta::Fp};
+/// use halo2_sudoku::{packed_instance, to_field_grid, MyCircuit};
+///
+/// let mut puzzle = vec![
+///     vec![5, 7, 1, 6, 2, 4, 3, 9, 8],
+///     vec![4, 2, 6, 3, 8, 9, 1, 5, 7],
+///     vec![3, 8, 9, 7, 5, 1, 2, 6, 4],
+///     vec![9, 5, 7, 4, 1, 2, 8, 3, 6],
+///     vec![1, 6, 3, 8, 9, 7, 5, 4, 2],
+///     vec![2, 4, 8, 5, 6, 3, 9, 7, 1],
+///     vec![8, 9, 4, 2, 7, 5, 6, 1, 3],
+///     vec![6, 3, 5, 1, 4, 8, 7, 2, 9],
+///     vec![7, 1, 2, 9, 3, 6, 4, 8, 5],
+/// ];
+/// // leave (4, 4) blank and prove knowledge of the 9 that goes there
+/// let mut solution = vec![vec![0; 9]; 9];
+/// solution[4][4] = puzzle[4][4];
+/// puzzle[4][4] = 0;
+///
+/// let circuit = MyCircuit::<Fp>::new(to_field_grid(solution)).unwrap();
+/// let instance = packed_instance(&to_field_grid(puzzle));
+/// let prover = MockProver::run(7, &circuit, instance).unwrap();
+/// prover.assert_satisfied();
+/// ```
+#[derive(Default, Clone)]
+pub struct MyCircuit<
+    F,
+    const N: usize = 9,
+    const BOX_ROWS: usize = 3,
+    const BOX_COLS: usize = BOX_ROWS,
+> {
+    pub(crate) solution: Vec<Vec<F>>,
+    /// Synthesize with [`SudokuChip::assign_chunked`] instead of a single
+    /// region.
+    pub(crate) chunked: bool,
+}
+
+/// [`MyCircuit`] under a name that says what it proves, for downstream
+/// crates: built the same way, e.g. with [`MyCircuit::build`].
+pub type SudokuCircuit<
+    F,
+    const N: usize = 9,
+    const BOX_ROWS: usize = 3,
+    const BOX_COLS: usize = BOX_ROWS,
+> = MyCircuit<F, N, BOX_ROWS, BOX_COLS>;
+
+impl<F> MyCircuit<F> {
+    /// `solution` is the private witness, indexed `[row][col]`: the digit in
+    /// every blank of the puzzle and zero at every clue position, which is
+    /// copied from the instance instead. Fails with
+    /// [`SudokuError::BadDimensions`] unless it is 9x9.
+    pub fn new(solution: Vec<Vec<F>>) -> Result<Self, SudokuError> {
+        Self::sized(solution)
+    }
+}
+
+impl<F: FieldExt> MyCircuit<F> {
+    /// Solves the puzzle `clues` (zero for blanks) and witnesses the
+    /// solution, so callers only supply the public puzzle. Fails with
+    /// [`SudokuError::Unsolvable`] if it has no solution; a puzzle with
+    /// several gets whichever one [`solve_puzzle`] finds.
+    pub fn from_clues(clues: &[[u8; 9]; 9]) -> Result<Self, SudokuError> {
+        let full = solve_puzzle(clues).ok_or(SudokuError::Unsolvable)?;
+        Self::new(solution_witness(clues, &full))
+    }
+
+    /// Like [`Self::from_clues`], but also returns the matching public input,
+    /// the clues packed as by [`packed_instance`], so the witness and the
+    /// instance cannot disagree about which cells are given. Pass it to
+    /// `MockProver::run` or `prove_with_params` as is, or its one column to
+    /// `prove`. A clue above 9 fails with [`SudokuError::ValueOutOfRange`].
+    pub fn build(clues: &[[u8; 9]; 9]) -> Result<(Self, Vec<Vec<F>>), SudokuError> {
+        let public = packed_instance(&Vec::<Vec<F>>::from(Grid(*clues)));
+        check_instance(&public[0], 9)?;
+        let circuit = Self::from_clues(clues)?;
+        Ok((circuit, public))
+    }
+}
+
+impl<F, const N: usize, const BOX_ROWS: usize, const BOX_COLS: usize>
+    MyCircuit<F, N, BOX_ROWS, BOX_COLS>
+{
+    /// Like [`MyCircuit::new`], for an `N x N` board with `BOX_ROWS x
+    /// BOX_COLS` boxes, e.g. `MyCircuit::<Fp, 4, 2>::sized(solution)` or
+    /// `MyCircuit::<Fp, 6, 2, 3>::sized(solution)`. `solution` must be
+    /// `N x N`; [`Default`] is only meant for `without_witnesses`.
+    pub fn sized(solution: Vec<Vec<F>>) -> Result<Self, SudokuError> {
+        let rows = solution.len();
+        let cols = solution
+            .iter()
+            .map(Vec::len)
+            .find(|&len| len != N)
+            .unwrap_or(N);
+        if rows != N || cols != N {
+            return Err(SudokuError::BadDimensions { rows, cols });
+        }
+
+        Ok(Self {
+            solution,
+            chunked: false,
+        })
+    }
+}
+
+impl<F: FieldExt, const N: usize, const BOX_ROWS: usize, const BOX_COLS: usize>
+    MyCircuit<F, N, BOX_ROWS, BOX_COLS>
+{
+    /// Like [`Self::sized`], but first checks `solution` against the
+    /// `puzzle` it will be proved for, both indexed `[row][col]`: every cell
+    /// must be given by exactly one of them. A cell zero in both would be
+    /// assigned from neither source and fails with
+    /// [`SudokuError::Incomplete`]; a cell nonzero in both fails with
+    /// [`SudokuError::ClueOverlap`].
+    pub fn for_puzzle(solution: Vec<Vec<F>>, puzzle: &[Vec<F>]) -> Result<Self, SudokuError> {
+        let circuit = Self::sized(solution)?;
+        let rows = puzzle.len();
+        let cols = puzzle
+            .iter()
+            .map(Vec::len)
+            .find(|&len| len != N)
+            .unwrap_or(N);
+        if rows != N || cols != N {
+            return Err(SudokuError::BadDimensions { rows, cols });
+        }
+
+        for (row, (givens, cells)) in puzzle.iter().zip(&circuit.solution).enumerate() {
+            for (col, (&given, &cell)) in givens.iter().zip(cells).enumerate() {
+                match (given == F::zero(), cell == F::zero()) {
+                    (true, true) => return Err(SudokuError::Incomplete { row, col }),
+                    (false, false) => return Err(SudokuError::ClueOverlap { row, col }),
+                    _ => {}
+                }
+            }
+        }
+        Ok(circuit)
+    }
+}
+
+impl<F: FieldExt, const N: usize, const BOX_ROWS: usize, const BOX_COLS: usize> Circuit<F>
+    for MyCircuit<F, N, BOX_ROWS, BOX_COLS>
+{
+    type Config = SudokuConfig<N, BOX_ROWS, BOX_COLS>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        SudokuChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = SudokuChip::construct(config);
+        if self.chunked {
+            chip.assign_chunked(layouter, &self.solution)?;
+        } else {
+            chip.assign(layouter, &self.solution)?;
+        }
+        Ok(())
+    }
+}
+
+/// Proves several 9x9 puzzles in one proof: each solution is laid out in
+/// its own block of nine rows of one region, checked by the same gates as
+/// [`MyCircuit`]. The public input is a single instance column holding every
+/// puzzle's [`packed_instance`](crate::io::packed_instance) one after the
+/// other.
+///
+/// A pack of `n` puzzles needs one proof and one verification instead of
+/// `n`, but `81 * n` instance rows, so `k` grows with the pack and each
+/// proof costs more than a single puzzle's. The keys fix both the number of
+/// puzzles and which of their cells are given.
+#[derive(Default, Clone)]
+pub struct BatchSudokuCircuit<F> {
+    pub solutions: Vec<Vec<Vec<F>>>,
+}
+
+impl<F: FieldExt> Circuit<F> for BatchSudokuCircuit<F> {
+    type Config = SudokuConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        SudokuChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        SudokuChip::construct(config).assign_batch(layouter, &self.solutions)?;
+        Ok(())
+    }
+}
+
+/// The boxes [`SamuraiCircuit`] grids share: `(a, corner_a, b, corner_b)`
+/// means the box with top-left cell `corner_a` of grid `a` is the box with
+/// top-left cell `corner_b` of grid `b`. Grid 2 is the centre.
+const SAMURAI_OVERLAPS: [(usize, Cell, usize, Cell); 4] = [
+    (0, (6, 6), 2, (0, 0)),
+    (1, (6, 0), 2, (0, 6)),
+    (3, (0, 6), 2, (6, 0)),
+    (4, (0, 0), 2, (6, 6)),
+];
+
+/// Samurai sudoku: five 9x9 grids, top left, top right, centre, bottom left
+/// and bottom right, where each corner grid shares its inner corner box
+/// with the centre grid. The grids are laid out and checked as in
+/// [`BatchSudokuCircuit`], with the same packed public input, and copy
+/// constraints tie the shared boxes' cells together.
+#[derive(Default, Clone)]
+pub struct SamuraiCircuit<F> {
+    /// The five witnesses, in the order above.
+    solutions: Vec<Vec<Vec<F>>>,
+}
+
+impl<F> SamuraiCircuit<F> {
+    /// `solutions` are the five witnesses, top left to bottom right, each as
+    /// for [`MyCircuit::new`]. Fails with [`SudokuError::BadGridCount`]
+    /// unless there are five and with [`SudokuError::BadDimensions`] unless
+    /// each is 9x9.
+    pub fn new(solutions: Vec<Vec<Vec<F>>>) -> Result<Self, SudokuError> {
+        if solutions.len() != SAMURAI_OVERLAPS.len() + 1 {
+            return Err(SudokuError::BadGridCount {
+                count: solutions.len(),
+                expected: SAMURAI_OVERLAPS.len() + 1,
+            });
+        }
+        let solutions = solutions
+            .into_iter()
+            .map(|solution| Ok(MyCircuit::new(solution)?.solution))
+            .collect::<Result<_, SudokuError>>()?;
+        Ok(Self { solutions })
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for SamuraiCircuit<F> {
+    type Config = SudokuConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        SudokuChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        // a circuit built without `new`, e.g. by `without_witnesses`, has no
+        // grids to tie together
+        if self.solutions.len() != SAMURAI_OVERLAPS.len() + 1 {
+            return Err(Error::Synthesis);
+        }
+        let grids = SudokuChip::construct(config)
+            .assign_batch(layouter.namespace(|| "grids"), &self.solutions)?;
+
+        layouter.assign_region(
+            || "shared boxes",
+            |mut region| {
+                for &(a, (row_a, col_a), b, (row_b, col_b)) in &SAMURAI_OVERLAPS {
+                    for i in 0..3 {
+                        for j in 0..3 {
+                            region.constrain_equal(
+                                grids[a][row_a + i][col_a + j].cell(),
+                                grids[b][row_b + i][col_b + j].cell(),
+                            )?;
+                        }
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+/// The selector and grid columns of a [`RangeOnlyCircuit`].
+#[derive(Debug, Clone)]
+pub struct RangeOnlyConfig {
+    selector: Selector,
+    advice: [Column<Advice>; 9],
+    digits: TableColumn,
+}
+
+/// A debugging circuit holding only the sudoku range check lookups: it accepts
+/// any grid of digits in `1..=9`, whether or not rows, columns and boxes are
+/// distinct, so a digit-out-of-range failure can be reproduced on its own. It
+/// has no instance columns.
+#[derive(Default)]
+pub struct RangeOnlyCircuit<F> {
+    grid: Vec<Vec<F>>,
+}
+
+impl<F: FieldExt> RangeOnlyCircuit<F> {
+    /// `grid` is the full 9x9 grid, indexed `[row][col]`.
+    pub fn new(grid: Vec<Vec<F>>) -> Self {
+        Self { grid }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for RangeOnlyCircuit<F> {
+    type Config = RangeOnlyConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let selector = meta.complex_selector();
+        let advice = [0; 9].map(|_| meta.advice_column());
+        let digits = SudokuChip::<F>::configure_range_check(
+            meta,
+            selector,
+            &advice,
+            &SudokuChip::<F>::classic_alphabet(),
+        );
+        RangeOnlyConfig {
+            selector,
+            advice,
+            digits,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        SudokuChip::<F>::load_digits(
+            &mut layouter,
+            config.digits,
+            &SudokuChip::<F>::classic_alphabet(),
+        )?;
+
+        layouter.assign_region(
+            || "range only",
+            |mut region| {
+                for row in 0..9 {
+                    config.selector.enable(&mut region, row)?;
+                }
+                for (row, (column, values)) in config.advice.iter().zip(&self.grid).enumerate() {
+                    for (col, &value) in values.iter().enumerate() {
+                        region.assign_advice(
+                            || format!("row {} col {}", row, col),
+                            *column,
+                            col,
+                            || Value::known(value),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+/// A "these two cells differ" gadget: witness `w`, the inverse of `a - b`,
+/// and constrain `(a - b) * w == 1`, which no `w` satisfies when `a == b`.
+/// [`Self::constraint`] is the gate fragment, which the anti-move and
+/// partial-grid gates embed with their own inverse columns;
+/// [`Self::assert_ne`] applies it to any two assigned cells, copying them
+/// into the gadget's columns one pair per row.
+#[derive(Debug, Clone, Copy)]
+pub struct NotEqualConfig {
+    selector: Selector,
+    a: Column<Advice>,
+    b: Column<Advice>,
+    inverse: Column<Advice>,
+}
+
+impl NotEqualConfig {
+    /// Allocates the gadget's columns and its "not equal" gate.
+    pub fn configure<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        let selector = meta.selector();
+        let [a, b, inverse] = [0; 3].map(|_| meta.advice_column());
+        meta.enable_equality(a);
+        meta.enable_equality(b);
+
+        meta.create_gate("not equal", |meta| {
+            let selector = meta.query_selector(selector);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let inverse = meta.query_advice(inverse, Rotation::cur());
+
+            vec![selector * Self::constraint(a, b, inverse)]
+        });
+
+        Self {
+            selector,
+            a,
+            b,
+            inverse,
+        }
+    }
+
+    /// `(a - b) * inverse - 1`, which vanishes only if `inverse` is the
+    /// inverse of `a - b`, so never when `a == b`.
+    pub fn constraint<F: FieldExt>(
+        a: Expression<F>,
+        b: Expression<F>,
+        inverse: Expression<F>,
+    ) -> Expression<F> {
+        (a - b) * inverse - Expression::Constant(F::one())
+    }
+
+    /// The witness [`Self::constraint`] needs: the inverse of `a - b`, or
+    /// zero when they are equal, which the constraint then rejects.
+    pub fn inverse<F: FieldExt>(a: Value<F>, b: Value<F>) -> Value<F> {
+        (a - b).map(|diff| diff.invert().unwrap_or(F::zero()))
+    }
+
+    /// Constrains `a != b` at `offset` of `region`. Both cells must be in
+    /// columns with equality enabled.
+    pub fn assert_ne<F: FieldExt>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        self.selector.enable(region, offset)?;
+        a.copy_advice(|| "a", region, self.a, offset)?;
+        b.copy_advice(|| "b", region, self.b, offset)?;
+        region.assign_advice(
+            || "inverse of a - b",
+            self.inverse,
+            offset,
+            || Self::inverse(a.value().copied(), b.value().copied()),
+        )?;
+        Ok(())
+    }
+}
+
+/// The columns of a [`PartialCircuit`].
+#[derive(Debug, Clone)]
+pub struct PartialConfig {
+    /// Enabled on every grid row, for the range check lookups.
+    always_enabled: Selector,
+    only_first_enabled: Selector,
+    /// Grid cell `(row, col)` is `advice[row]` at region row `col`, as in
+    /// [`SudokuConfig`], and `filled[row]` there is 1 if it is filled in.
+    advice: [Column<Advice>; 9],
+    filled: [Column<Advice>; 9],
+    instance: Column<Instance>,
+    givens: [Column<Advice>; 9],
+    digits: TableColumn,
+    /// Every pair of cells sharing a unit; pair `i` witnesses the inverse of
+    /// its difference in `inverses[i / 9]` at region row `i % 9`.
+    pairs: Vec<(Cell, Cell)>,
+    inverses: Vec<Column<Advice>>,
+}
+
+/// Proves that a partly filled grid has no mistakes so far: every filled
+/// cell holds a digit, no two filled cells in a row, column or box match, and
+/// every clue of the puzzle, packed as by [`packed_instance`], is filled in
+/// with its value. Empty cells need not be anything, so a fully empty grid
+/// against an empty puzzle is valid.
+///
+/// Each cell carries a boolean "filled" flag next to its value; an empty cell
+/// has flag 0 and value 0. Each of the 810 pairs of cells sharing a unit gets
+/// `f_a * f_b * ((a - b) * w - 1) == 0`, with `w` a witnessed inverse of
+/// `a - b`, so only pairs of filled cells must differ.
+#[derive(Default)]
+pub struct PartialCircuit<F> {
+    grid: Vec<Vec<Option<F>>>,
+}
+
+impl<F: FieldExt> PartialCircuit<F> {
+    /// `grid` is 9x9, indexed `[row][col]`, with `None` for the cells not
+    /// filled in yet, clues included. Fails with
+    /// [`SudokuError::BadDimensions`] unless it is 9x9.
+    pub fn new(grid: Vec<Vec<Option<F>>>) -> Result<Self, SudokuError> {
+        let rows = grid.len();
+        let cols = grid.iter().map(Vec::len).find(|&len| len != 9).unwrap_or(9);
+        if rows != 9 || cols != 9 {
+            return Err(SudokuError::BadDimensions { rows, cols });
+        }
+        Ok(Self { grid })
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for PartialCircuit<F> {
+    type Config = PartialConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let always_enabled = meta.complex_selector();
+        let only_first_enabled = meta.selector();
+        let advice = [0; 9].map(|_| meta.advice_column());
+        let filled = [0; 9].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        let givens = [0; 9].map(|_| meta.advice_column());
+        let digits = meta.lookup_table_column();
+
+        meta.enable_equality(instance);
+        for given in givens {
+            meta.enable_equality(given);
+        }
+
+        // as in `SudokuChip::configure_range_check`, but empty cells are
+        // looked up as 1 too
+        for (column, flag) in advice.into_iter().zip(filled) {
+            meta.lookup(|meta| {
+                let always_enabled = meta.query_selector(always_enabled);
+                let value = meta.query_advice(column, Rotation::cur());
+                let flag = meta.query_advice(flag, Rotation::cur());
+                let one = Expression::Constant(F::one());
+
+                vec![(always_enabled * flag * (value - one.clone()) + one, digits)]
+            });
+        }
+
+        meta.create_gate("partial cells", |meta| {
+            let only_first_enabled = meta.query_selector(only_first_enabled);
+            let one = Expression::Constant(F::one());
+
+            let mut constraints = Vec::new();
+            for row in 0..9 {
+                for col in 0..9 {
+                    let rotation = Rotation(col as i32);
+                    let value = meta.query_advice(advice[row], rotation);
+                    let flag = meta.query_advice(filled[row], rotation);
+                    let given = meta.query_advice(givens[row], rotation);
+
+                    constraints.extend([
+                        flag.clone() * (one.clone() - flag.clone()),
+                        (one.clone() - flag) * value.clone(),
+                        given.clone() * (given - value),
+                    ]);
+                }
+            }
+            constraints
+                .into_iter()
+                .map(|constraint| only_first_enabled.clone() * constraint)
+                .collect::<Vec<_>>()
+        });
+
+        let cells: Vec<Cell> = (0..81).map(|i| (i / 9, i % 9)).collect();
+        let mut pairs = Vec::new();
+        for (i, &a) in cells.iter().enumerate() {
+            for &b in &cells[i + 1..] {
+                if a.0 == b.0 || a.1 == b.1 || (a.0 / 3, a.1 / 3) == (b.0 / 3, b.1 / 3) {
+                    pairs.push((a, b));
+                }
+            }
+        }
+        let inverses: Vec<_> = (0..pairs.len().div_ceil(9))
+            .map(|_| meta.advice_column())
+            .collect();
+
+        meta.create_gate("partial units", |meta| {
+            let only_first_enabled = meta.query_selector(only_first_enabled);
+
+            let mut constraints = Vec::with_capacity(pairs.len());
+            for (i, &((ra, ca), (rb, cb))) in pairs.iter().enumerate() {
+                let a = meta.query_advice(advice[ra], Rotation(ca as i32));
+                let b = meta.query_advice(advice[rb], Rotation(cb as i32));
+                let a_filled = meta.query_advice(filled[ra], Rotation(ca as i32));
+                let b_filled = meta.query_advice(filled[rb], Rotation(cb as i32));
+                let inverse = meta.query_advice(inverses[i / 9], Rotation((i % 9) as i32));
+
+                constraints.push(
+                    only_first_enabled.clone()
+                        * a_filled
+                        * b_filled
+                        * NotEqualConfig::constraint(a, b, inverse),
+                );
+            }
+            constraints
+        });
+
+        PartialConfig {
+            always_enabled,
+            only_first_enabled,
+            advice,
+            filled,
+            instance,
+            givens,
+            digits,
+            pairs,
+            inverses,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        SudokuChip::<F>::load_digits(
+            &mut layouter,
+            config.digits,
+            &SudokuChip::<F>::classic_alphabet(),
+        )?;
+
+        layouter.assign_region(
+            || "partial grid",
+            |mut region| {
+                config.only_first_enabled.enable(&mut region, 0)?;
+                for row in 0..9 {
+                    config.always_enabled.enable(&mut region, row)?;
+                }
+
+                for (row, cells) in self.grid.iter().enumerate() {
+                    for (col, cell) in cells.iter().enumerate() {
+                        region.assign_advice(
+                            || format!("row {} col {}", row, col),
+                            config.advice[row],
+                            col,
+                            || Value::known(cell.unwrap_or(F::zero())),
+                        )?;
+                        region.assign_advice(
+                            || format!("row {} col {} filled", row, col),
+                            config.filled[row],
+                            col,
+                            || Value::known(if cell.is_some() { F::one() } else { F::zero() }),
+                        )?;
+                        region.assign_advice_from_instance(
+                            || format!("copy given row {} col {}", row, col),
+                            config.instance,
+                            row * 9 + col,
+                            config.givens[row],
+                            col,
+                        )?;
+                    }
+                }
+
+                for (i, &((ra, ca), (rb, cb))) in config.pairs.iter().enumerate() {
+                    let inverse = match (self.grid[ra][ca], self.grid[rb][cb]) {
+                        (Some(a), Some(b)) => {
+                            NotEqualConfig::inverse(Value::known(a), Value::known(b))
+                        }
+                        _ => Value::known(F::zero()),
+                    };
+                    region.assign_advice(
+                        || format!("inverse of ({},{}) - ({},{})", ra, ca, rb, cb),
+                        config.inverses[i / 9],
+                        i % 9,
+                        || inverse,
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+/// A reminder for integrators, if circuit `C` checks units with the unsound
+/// [`UniquenessMode::SumProduct`]; `None` under
+/// [`UniquenessMode::Permutation`].
+///
+/// Only circuits configured as a bare [`SudokuConfig`] can be asked, such as
+/// [`MyCircuit`], [`BatchSudokuCircuit`] and [`SamuraiCircuit`]. Those that
+/// wrap one in a config of their own, like [`HashedCircuit`] and
+/// [`CommittedCircuit`], always use [`SudokuChip::configure`] and so
+/// [`UniquenessMode::Permutation`]; [`PartialCircuit`] checks every pair of
+/// cells in a unit for distinctness instead, which is sound.
+pub fn soundness_warning<F: FieldExt, C: Circuit<F, Config = SudokuConfig>>() -> Option<String> {
+    let mut cs = ConstraintSystem::default();
+    match C::configure(&mut cs).uniqueness() {
+        UniquenessMode::SumProduct => Some(
+            "rows, columns and boxes are checked by sum and product only, which \
+             accepts non-permutations such as {1, 2, 4, 4, 4, 5, 7, 9, 9}; configure \
+             UniquenessMode::Permutation for a sound circuit"
+                .to_string(),
+        ),
+        UniquenessMode::Permutation => None,
+    }
+}
+
+/// A 32-byte BLAKE2b digest of the constraint system `C` configures: column
+/// counts, every gate expression, the selector layout, lookups and the
+/// permutation columns. Any change to the gate set changes the digest, so
+/// provers and verifiers can pin the exact circuit they expect.
+pub fn circuit_commitment<F: FieldExt, C: Circuit<F>>() -> [u8; 32] {
+    let mut cs = ConstraintSystem::<F>::default();
+    C::configure(&mut cs);
+
+    let digest = blake2b_simd::Params::new()
+        .hash_length(32)
+        .personal(b"halo2-sudoku-cs")
+        .hash(format!("{:?}", cs.pinned()).as_bytes());
+
+    let mut commitment = [0; 32];
+    commitment.copy_from_slice(digest.as_bytes());
+    commitment
+}
+
+/// How many bits of [`puzzle_index`] a [`CommittedCircuit`] derives from the
+/// server randomness.
+const SELECTION_BITS: usize = 2;
+
+/// How many puzzles the table of a [`CommittedCircuit`] holds, one for every
+/// value of [`puzzle_index`].
+pub const COMMITTED_PUZZLES: usize = 1 << SELECTION_BITS;
+
+/// A quadratic non-residue of the Pallas base field: 5 generates its
+/// multiplicative group.
+const NON_RESIDUE: u64 = 5;
+
+/// Keyed PRF: Poseidon (P128Pow5T3, as for [`HashedCircuit`]) over
+/// `[key, message]`. The host-side twin of the circuit's "prf" regions.
+pub fn prf(key: Fp, message: Fp) -> Fp {
+    poseidon::Hash::<_, P128Pow5T3, ConstantLength<2>, 3, 2>::init().hash([key, message])
+}
+
+/// The commitment to the server's `randomness` that a [`CommittedCircuit`]
+/// exposes, `prf(randomness, 0)`. The server publishes it before assigning
+/// any puzzle.
+pub fn randomness_commitment(randomness: Fp) -> Fp {
+    prf(randomness, Fp::zero())
+}
+
+/// Whether `value` is a quadratic non-residue, with the square root the
+/// "selection bit" gate checks: of `value` itself for a residue, and of
+/// `NON_RESIDUE * value` otherwise.
+fn residue_bit(value: Fp) -> (bool, Fp) {
+    match Option::<Fp>::from(value.sqrt()) {
+        Some(root) => (false, root),
+        None => {
+            let scaled = value * Fp::from(NON_RESIDUE);
+            (
+                true,
+                Option::from(scaled.sqrt()).expect("NON_RESIDUE is a non-residue"),
+            )
+        }
+    }
+}
+
+/// Which puzzle of a [`CommittedCircuit`]'s table `randomness` selects: bit
+/// `j` is set when `prf(randomness, j + 1)` is not a square. The host-side
+/// twin of the circuit's "selection bit" gates.
+pub fn puzzle_index(randomness: Fp) -> usize {
+    (0..SELECTION_BITS)
+        .filter(|&bit| residue_bit(prf(randomness, Fp::from(bit as u64 + 1))).0)
+        .map(|bit| 1 << bit)
+        .sum()
+}
+
+/// Derives the puzzle index from PRF outputs and checks the instance puzzle
+/// against the table entry it picks. One row per bit, for `y = prf(r, j + 1)`:
+///
+///   value | root | bit | split
+///    y    |  w   |  b  |   1
+///
+/// with `b` boolean and `w^2 = y * (1 + (NON_RESIDUE - 1) * b)`, and one row
+/// per cell `i` of the puzzle:
+///
+///   cell | b_0 | b_1 | table_0  ...  table_3 | select
+///    g_i | b_0 | b_1 | t_0[i]   ...  t_3[i]  |   1
+///
+/// with `g_i` copied from the instance and equal to `t_{b_0 + 2 b_1}[i]`.
+#[derive(Debug, Clone)]
+pub(crate) struct SelectConfig {
+    split: Selector,
+    select: Selector,
+    advice: [Column<Advice>; SELECTION_BITS + 1],
+    table: [Column<Fixed>; COMMITTED_PUZZLES],
+}
+
+impl SelectConfig {
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self {
+        let split = meta.selector();
+        let select = meta.selector();
+        let advice = [0; SELECTION_BITS + 1].map(|_| meta.advice_column());
+        let table = [0; COMMITTED_PUZZLES].map(|_| meta.fixed_column());
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("selection bit", |meta| {
+            let q = meta.query_selector(split);
+            let value = meta.query_advice(advice[0], Rotation::cur());
+            let root = meta.query_advice(advice[1], Rotation::cur());
+            let b = meta.query_advice(advice[2], Rotation::cur());
+
+            let one = Expression::Constant(Fp::one());
+            let scale = one.clone() + b.clone() * Expression::Constant(Fp::from(NON_RESIDUE - 1));
+            vec![
+                q.clone() * b.clone() * (one - b),
+                q * (root.clone() * root - value * scale),
+            ]
+        });
+
+        meta.create_gate("select puzzle", |meta| {
+            let q = meta.query_selector(select);
+            let cell = meta.query_advice(advice[0], Rotation::cur());
+            let bits: Vec<_> = advice[1..]
+                .iter()
+                .map(|&column| meta.query_advice(column, Rotation::cur()))
+                .collect();
+
+            // the entry of the table whose index the bits spell, as a sum of
+            // each entry times the indicator of its index
+            let one = Expression::Constant(Fp::one());
+            let picked = table
+                .iter()
+                .enumerate()
+                .map(|(index, &column)| {
+                    let entry = meta.query_fixed(column, Rotation::cur());
+                    bits.iter().enumerate().fold(entry, |term, (j, b)| {
+                        if index >> j & 1 == 1 {
+                            term * b.clone()
+                        } else {
+                            term * (one.clone() - b.clone())
+                        }
+                    })
+                })
+                .reduce(|sum, term| sum + term)
+                .expect("COMMITTED_PUZZLES > 0");
+
+            vec![q * (cell - picked)]
+        });
+
+        Self {
+            split,
+            select,
+            advice,
+            table,
+        }
+    }
+
+    /// Assigns the bit `residue_bit` derives from the PRF output `value` and
+    /// returns its cell.
+    fn assign_bit(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        value: &AssignedCell<Fp, Fp>,
+    ) -> Result<AssignedCell<Fp, Fp>, Error> {
+        layouter.assign_region(
+            || "selection bit",
+            |mut region| {
+                self.split.enable(&mut region, 0)?;
+                value.copy_advice(|| "prf output", &mut region, self.advice[0], 0)?;
+
+                let parts = value.value().map(|&value| residue_bit(value));
+                region.assign_advice(
+                    || "root",
+                    self.advice[1],
+                    0,
+                    || parts.map(|(_, root)| root),
+                )?;
+                region.assign_advice(
+                    || "bit",
+                    self.advice[2],
+                    0,
+                    || parts.map(|(bit, _)| Fp::from(bit)),
+                )
+            },
+        )
+    }
+
+    /// Copies the 81 cells of `instance` and checks them against the entry of
+    /// `puzzles` that `bits` select.
+    fn assign_puzzle(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        instance: Column<Instance>,
+        bits: &[AssignedCell<Fp, Fp>],
+        puzzles: &[[[u8; 9]; 9]; COMMITTED_PUZZLES],
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "select puzzle",
+            |mut region| {
+                for cell in 0..81 {
+                    self.select.enable(&mut region, cell)?;
+                    region.assign_advice_from_instance(
+                        || format!("cell {}", cell),
+                        instance,
+                        cell,
+                        self.advice[0],
+                        cell,
+                    )?;
+                    for (bit, &column) in bits.iter().zip(&self.advice[1..]) {
+                        bit.copy_advice(|| "bit", &mut region, column, cell)?;
+                    }
+                    for (puzzle, &column) in puzzles.iter().zip(&self.table) {
+                        let entry = Fp::from(u64::from(puzzle[cell / 9][cell % 9]));
+                        region.assign_fixed(
+                            || format!("table cell {}", cell),
+                            column,
+                            cell,
+                            || Value::known(entry),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CommittedConfig {
+    sudoku: SudokuConfig,
+    poseidon: Pow5Config<Fp, 3, 2>,
+    select: SelectConfig,
+
+    randomness: Column<Advice>,
+    output: Column<Instance>,
+}
+
+/// A sudoku proof for a puzzle derived from server randomness committed to
+/// in advance, for fair puzzle assignment. The server publishes
+/// [`randomness_commitment`] of its private randomness `r` before seeing the
+/// player; the puzzle is then entry [`puzzle_index`] of `r` in a fixed table
+/// of [`COMMITTED_PUZZLES`] puzzles, which the keys pin like the rest of the
+/// circuit.
+///
+/// The circuit recomputes the commitment from `r`, derives the index bits
+/// from `prf(r, 1)` and `prf(r, 2)` in-circuit, and checks the instance
+/// puzzle against the table entry they select (see
+/// [`committed_instance`](crate::io::committed_instance)). The server cannot
+/// pick an easier puzzle after the fact without opening the commitment to
+/// another `r`.
+#[derive(Clone)]
+pub struct CommittedCircuit {
+    puzzles: [[[u8; 9]; 9]; COMMITTED_PUZZLES],
+    solution: Vec<Vec<Fp>>,
+    randomness: Value<Fp>,
+}
+
+impl CommittedCircuit {
+    /// `puzzles` is the table to pick from, zero for blanks, `solution` the
+    /// witness for the picked puzzle as for [`MyCircuit::new`] and
+    /// `randomness` the server's private randomness. A table clue above 9
+    /// fails with [`SudokuError::ValueOutOfRange`].
+    pub fn new(
+        puzzles: [[[u8; 9]; 9]; COMMITTED_PUZZLES],
+        solution: Vec<Vec<Fp>>,
+        randomness: Fp,
+    ) -> Result<Self, SudokuError> {
+        for puzzle in &puzzles {
+            let cells: Vec<Fp> = Vec::<Vec<Fp>>::from(Grid(*puzzle)).concat();
+            check_instance(&cells, 9)?;
+        }
+        Ok(Self {
+            puzzles,
+            solution: MyCircuit::new(solution)?.solution,
+            randomness: Value::known(randomness),
+        })
+    }
+}
+
+impl Circuit<Fp> for CommittedCircuit {
+    type Config = CommittedConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            puzzles: self.puzzles,
+            solution: Vec::new(),
+            randomness: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let sudoku = SudokuChip::configure(meta);
+
+        let state = [0; 3].map(|_| meta.advice_column());
+        let partial_sbox = meta.advice_column();
+        let rc_a = [0; 3].map(|_| meta.fixed_column());
+        let rc_b = [0; 3].map(|_| meta.fixed_column());
+        // the sponge's padding and the PRF messages are assigned from
+        // constants
+        meta.enable_constant(rc_b[0]);
+        let poseidon = Pow5Chip::configure::<P128Pow5T3>(meta, state, partial_sbox, rc_a, rc_b);
+
+        let select = SelectConfig::configure(meta);
+        let randomness = meta.advice_column();
+        meta.enable_equality(randomness);
+        let output = meta.instance_column();
+        meta.enable_equality(output);
+
+        CommittedConfig {
+            sudoku,
+            poseidon,
+            select,
+            randomness,
+            output,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let instance = config.sudoku.instance;
+        let chip = SudokuChip::construct(config.sudoku);
+        chip.assign(layouter.namespace(|| "sudoku"), &self.solution)?;
+
+        let randomness = layouter.assign_region(
+            || "randomness",
+            |mut region| region.assign_advice(|| "r", config.randomness, 0, || self.randomness),
+        )?;
+        // prf(r, message) for a constant message
+        let mut assign_prf = |message: u64| {
+            let message = layouter.assign_region(
+                || format!("prf message {}", message),
+                |mut region| {
+                    region.assign_advice_from_constant(
+                        || "message",
+                        config.randomness,
+                        0,
+                        Fp::from(message),
+                    )
+                },
+            )?;
+            let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<2>, 3, 2>::init(
+                Pow5Chip::construct(config.poseidon.clone()),
+                layouter.namespace(|| format!("init prf {}", message)),
+            )?;
+            hasher.hash(
+                layouter.namespace(|| format!("prf {}", message)),
+                [randomness.clone(), message],
+            )
+        };
+
+        let commitment = assign_prf(0)?;
+        let outputs = (1..=SELECTION_BITS as u64)
+            .map(&mut assign_prf)
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let bits = outputs
+            .iter()
+            .map(|output| {
+                config
+                    .select
+                    .assign_bit(layouter.namespace(|| "selection bit"), output)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        config.select.assign_puzzle(
+            layouter.namespace(|| "select puzzle"),
+            instance,
+            &bits,
+            &self.puzzles,
+        )?;
+
+        layouter.constrain_instance(commitment.cell(), config.output, 0)
+    }
+}
+
+/// The columns of a [`HashedCircuit`].
+#[derive(Debug, Clone)]
+pub struct HashedConfig {
+    sudoku: SudokuConfig,
+    poseidon: Pow5Config<Fp, 3, 2>,
+
+    salt: Column<Advice>,
+    hash: Column<Instance>,
+}
+
+/// A sudoku proof that also commits to the whole solved grid: the 81 cells
+/// [`SudokuChip::assign`] returns, givens included, are Poseidon-hashed in
+/// the circuit together with a private salt, and the hash is exposed in a
+/// second instance column (see [`solution_hash`](crate::io::solution_hash)
+/// and [`hashed_instance`](crate::io::hashed_instance)).
+///
+/// The clues of a proper puzzle fix its solution, so without the salt anyone
+/// could hash the one completion and the hash would hide nothing. Under a
+/// salt the verifier never sees, two proofs carrying the same hash are of
+/// the same grid, which neither reveals; hashes are only comparable between
+/// provers sharing a salt, such as one a race organiser hands to every
+/// entrant and keeps from the public.
+#[derive(Default, Clone)]
+pub struct HashedCircuit {
+    solution: Vec<Vec<Fp>>,
+    salt: Value<Fp>,
+}
+
+impl HashedCircuit {
+    /// Like [`MyCircuit::new`]: `solution` is the witness, zero at every
+    /// given, and `salt` the private blinding hashed after the grid.
+    pub fn new(solution: Vec<Vec<Fp>>, salt: Fp) -> Result<Self, SudokuError> {
+        let MyCircuit { solution, .. } = MyCircuit::new(solution)?;
+        Ok(Self {
+            solution,
+            salt: Value::known(salt),
+        })
+    }
+}
+
+impl Circuit<Fp> for HashedCircuit {
+    type Config = HashedConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let sudoku = SudokuChip::configure(meta);
+
+        let state = [0; 3].map(|_| meta.advice_column());
+        let partial_sbox = meta.advice_column();
+        let rc_a = [0; 3].map(|_| meta.fixed_column());
+        let rc_b = [0; 3].map(|_| meta.fixed_column());
+        // the sponge's padding is assigned from a constant
+        meta.enable_constant(rc_b[0]);
+        let poseidon = Pow5Chip::configure::<P128Pow5T3>(meta, state, partial_sbox, rc_a, rc_b);
+
+        let salt = meta.advice_column();
+        meta.enable_equality(salt);
+        let hash = meta.instance_column();
+        meta.enable_equality(hash);
+
+        HashedConfig {
+            sudoku,
+            poseidon,
+            salt,
+            hash,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let chip = SudokuChip::construct(config.sudoku);
+        let grid = chip.assign(layouter.namespace(|| "sudoku"), &self.solution)?;
+        let salt = layouter.assign_region(
+            || "salt",
+            |mut region| region.assign_advice(|| "salt", config.salt, 0, || self.salt),
+        )?;
+        let cells: [AssignedCell<Fp, Fp>; 82] = grid
+            .into_iter()
+            .flatten()
+            .chain([salt])
+            .collect::<Vec<_>>()
+            .try_into()
+            .map_err(|_| Error::Synthesis)?;
+
+        let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<82>, 3, 2>::init(
+            Pow5Chip::construct(config.poseidon),
+            layouter.namespace(|| "init"),
+        )?;
+        let hash = hasher.hash(layouter.namespace(|| "solution hash"), cells)?;
+        layouter.constrain_instance(hash.cell(), config.hash, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        circuit_commitment, puzzle_index, randomness_commitment, soundness_warning,
+        BatchSudokuCircuit, Cell4, CircuitVersion, CommittedCircuit, Entry, HashedCircuit, Layout,
+        LayoutInfo, LineAggregate, MyCircuit, NotEqualConfig, PartialCircuit, RangeOnlyCircuit,
+        SamuraiCircuit, SudokuChip, SudokuConfig, UniquenessMode, COMMITTED_PUZZLES,
+    };
+    use crate::diagnostics::{diagnose, failure_report, GateFailure, GridLocation};
+    use crate::error::SudokuError;
+    use crate::fixtures::{example_arrays, example_grids};
+    use crate::io::{
+        committed_instance, hashed_instance, merge_grid, solution_hash, solution_witness, Grid,
+    };
+    use crate::solver::to_vec_grid;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
+    };
+    use proptest::prelude::*;
+    use std::{cell::RefCell, collections::HashMap, marker::PhantomData};
+
+    #[test]
+    fn sudoku_example() {
+        let k = 7;
+
+        let public_grid = vec![
+            vec![0, 0, 1, 0, 0, 4, 0, 9, 0],
+            vec![4, 0, 0, 0, 0, 0, 1, 0, 7],
+            vec![0, 8, 0, 7, 0, 0, 0, 0, 4],
+            vec![9, 0, 0, 0, 1, 0, 8, 0, 0],
+            vec![0, 0, 0, 8, 0, 7, 0, 0, 0],
+            vec![0, 0, 8, 0, 6, 0, 0, 0, 1],
+            vec![8, 0, 0, 0, 0, 5, 0, 1, 0],
+            vec![6, 0, 5, 0, 0, 0, 0, 0, 9],
+            vec![0, 1, 0, 9, 0, 0, 4, 0, 0],
+        ];
+
+        let solution = vec![
+            vec![5, 7, 0, 6, 2, 0, 3, 0, 8],
+            vec![0, 2, 6, 3, 8, 9, 0, 5, 0],
+            vec![3, 0, 9, 0, 5, 1, 2, 6, 0],
+            vec![0, 5, 7, 4, 0, 2, 0, 3, 6],
+            vec![1, 6, 3, 0, 9, 0, 5, 4, 2],
+            vec![2, 4, 0, 5, 0, 3, 9, 7, 0],
+            vec![0, 9, 4, 2, 7, 0, 6, 0, 3],
+            vec![0, 3, 0, 1, 4, 8, 7, 2, 0],
+            vec![7, 0, 2, 0, 3, 6, 0, 8, 5],
+        ];
+
+        let mut public_input = packed(public_grid);
+        let private_input = u64_grid_to_fp_grid(solution);
+
+        let circuit = MyCircuit::<Fp> {
+            solution: private_input.clone(),
+            chunked: false,
+        };
+
+        let prover = MockProver::run(k, &circuit, public_input.clone()).unwrap();
+        prover.assert_satisfied();
+
+        // (0, 0) is private in this witness, but the "givens" gate still binds
+        // a nonzero instance value there
+        public_input[0][0] += Fp::one();
+        let prover = MockProver::run(k, &circuit, public_input).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn samurai_overlaps() {
+        // one 21x21 pattern that is a valid sudoku in each of the five
+        // windows, whose top-left corners are a multiple of three apart
+        let pattern =
+            |row: usize, col: usize| ((3 * (row % 3) + (row / 3) % 3 + col) % 9 + 1) as u64;
+        let corners = [(0, 0), (0, 12), (6, 6), (12, 0), (12, 12)];
+        let grids: Vec<Vec<Vec<u64>>> = corners
+            .iter()
+            .map(|&(top, left)| {
+                (0..9)
+                    .map(|row| (0..9).map(|col| pattern(top + row, left + col)).collect())
+                    .collect()
+            })
+            .collect();
+
+        let run = |grids: Vec<Vec<Vec<u64>>>| {
+            let circuit =
+                SamuraiCircuit::new(grids.into_iter().map(u64_grid_to_fp_grid).collect()).unwrap();
+            MockProver::run(9, &circuit, vec![vec![Fp::zero(); 5 * 81]])
+                .unwrap()
+                .verify()
+                .is_ok()
+        };
+
+        assert!(run(grids.clone()));
+
+        // relabelling 1 and 2 keeps the top-right grid valid on its own, but
+        // its shared box no longer matches the centre's
+        let mut mismatched = grids;
+        for value in mismatched[1].iter_mut().flatten() {
+            *value = match *value {
+                1 => 2,
+                2 => 1,
+                v => v,
+            };
+        }
+        assert!(!run(mismatched));
+    }
+
+    #[test]
+    fn samurai_needs_five_grids() {
+        let grid = || u64_grid_to_fp_grid(example_grids().1);
+        assert!(matches!(
+            SamuraiCircuit::new(vec![grid(); 4]),
+            Err(SudokuError::BadGridCount {
+                count: 4,
+                expected: 5
+            })
+        ));
+        let mut short = vec![grid(); 5];
+        short[3].pop();
+        assert!(matches!(
+            SamuraiCircuit::new(short),
+            Err(SudokuError::BadDimensions { rows: 8, cols: 9 })
+        ));
+
+        // a circuit without its grids fails synthesis instead of panicking
+        assert!(matches!(
+            MockProver::run(9, &SamuraiCircuit::<Fp>::default(), vec![vec![]]),
+            Err(Error::Synthesis)
+        ));
+    }
+
+    #[test]
+    fn batch_of_two() {
+        // the example and its transpose, which is just as valid
+        let (public_grid, solution) = example_grids();
+        let transpose = |grid: &Vec<Vec<u64>>| -> Vec<Vec<u64>> {
+            (0..9)
+                .map(|col| grid.iter().map(|row| row[col]).collect())
+                .collect()
+        };
+        let public_grids = vec![public_grid.clone(), transpose(&public_grid)];
+        let solutions = vec![solution.clone(), transpose(&solution)];
+
+        let run = |solutions: Vec<Vec<Vec<u64>>>| {
+            let circuit = BatchSudokuCircuit {
+                solutions: solutions.into_iter().map(u64_grid_to_fp_grid).collect(),
+            };
+            let instance = public_grids
+                .iter()
+                .flatten()
+                .flatten()
+                .map(|&v| Fp::from(v));
+            MockProver::run(8, &circuit, vec![instance.collect()])
+                .unwrap()
+                .verify()
+                .is_ok()
+        };
+
+        assert!(run(solutions.clone()));
+
+        // swapping two private cells of the second grid keeps its row 0
+        // distinct but repeats 1 and 2 in its columns 4 and 5
+        let mut invalid = solutions;
+        invalid[1][0].swap(4, 5);
+        assert!(!run(invalid));
+    }
+
+    /// Records the values of the cells [`SudokuChip::assign`] returns.
+    struct ReadBackCircuit {
+        solution: Vec<Vec<Fp>>,
+        read: RefCell<Vec<Vec<Fp>>>,
+    }
+
+    impl Circuit<Fp> for ReadBackCircuit {
+        type Config = SudokuConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                solution: Vec::new(),
+                read: RefCell::default(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            SudokuChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let grid = SudokuChip::construct(config).assign(layouter, &self.solution)?;
+            let mut read = self.read.borrow_mut();
+            read.clear();
+            for cells in &grid {
+                let mut row = Vec::new();
+                for cell in cells {
+                    cell.value().map(|&value| row.push(value));
+                }
+                read.push(row);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn assigned_cells_hold_the_grid() {
+        let (public_grid, solution) = example_grids();
+        let circuit = ReadBackCircuit {
+            solution: u64_grid_to_fp_grid(solution),
+            read: RefCell::default(),
+        };
+        MockProver::run(7, &circuit, packed(public_grid))
+            .unwrap()
+            .assert_satisfied();
+
+        let read = circuit.read.borrow();
+        // (0, 0) is witnessed and (0, 2) copied from the instance
+        assert_eq!(read[0][0], Fp::from(5));
+        assert_eq!(read[0][2], Fp::from(1));
+        assert_eq!(read.iter().map(Vec::len).sum::<usize>(), 81);
+    }
+
+    struct DiagonalCircuit {
+        solution: Vec<Vec<Fp>>,
+    }
+
+    impl Circuit<Fp> for DiagonalCircuit {
+        type Config = SudokuConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                solution: Vec::new(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let mut config = SudokuChip::configure(meta);
+            SudokuChip::configure_diagonals(meta, &mut config);
+            config
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            SudokuChip::construct(config).assign(layouter, &self.solution)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn x_sudoku_diagonals() {
+        let run = |diagonal: bool, puzzle: Vec<Vec<u64>>, solution: Vec<Vec<u64>>| {
+            let solution = u64_grid_to_fp_grid(solution);
+            let prover = if diagonal {
+                MockProver::run(7, &DiagonalCircuit { solution }, packed(puzzle))
+            } else {
+                MockProver::run(7, &MyCircuit::<Fp>::new(solution).unwrap(), packed(puzzle))
+            };
+            prover.unwrap().verify().is_ok()
+        };
+
+        // both diagonals hold 1..9; every cell is private
+        let x_sudoku = vec![
+            vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
+            vec![4, 5, 6, 7, 8, 9, 1, 2, 3],
+            vec![7, 8, 9, 1, 2, 3, 4, 5, 6],
+            vec![2, 1, 4, 3, 6, 5, 8, 9, 7],
+            vec![3, 6, 8, 9, 7, 2, 5, 1, 4],
+            vec![5, 9, 7, 8, 1, 4, 6, 3, 2],
+            vec![9, 4, 1, 6, 3, 8, 2, 7, 5],
+            vec![8, 3, 2, 5, 4, 7, 9, 6, 1],
+            vec![6, 7, 5, 2, 9, 1, 3, 4, 8],
+        ];
+        assert!(run(true, vec![vec![0; 9]; 9], x_sudoku.clone()));
+        assert!(run(false, vec![vec![0; 9]; 9], x_sudoku));
+
+        // the example repeats 9 at (2, 2) and (4, 4)
+        let (public_grid, solution) = example_grids();
+        assert!(run(false, public_grid.clone(), solution.clone()));
+        assert!(!run(true, public_grid, solution));
+    }
+
+    struct AntiMoveCircuit<const KNIGHT: bool, const KING: bool> {
+        solution: Vec<Vec<Fp>>,
+    }
+
+    impl<const KNIGHT: bool, const KING: bool> Circuit<Fp> for AntiMoveCircuit<KNIGHT, KING> {
+        type Config = SudokuConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                solution: Vec::new(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let mut config = SudokuChip::configure(meta);
+            SudokuChip::configure_anti_moves(meta, &mut config, KNIGHT, KING);
+            config
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            SudokuChip::construct(config).assign(layouter, &self.solution)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn anti_knight_and_anti_king() {
+        fn run<const KNIGHT: bool, const KING: bool>(
+            puzzle: &[Vec<u64>],
+            solution: &[Vec<u64>],
+        ) -> bool {
+            let circuit = AntiMoveCircuit::<KNIGHT, KING> {
+                solution: u64_grid_to_fp_grid(solution.to_vec()),
+            };
+            MockProver::run(7, &circuit, packed(puzzle.to_vec()))
+                .unwrap()
+                .verify()
+                .is_ok()
+        }
+
+        // no two cells a knight's or a king's move apart match; every cell
+        // is private
+        let blank = vec![vec![0; 9]; 9];
+        let anti = vec![
+            vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
+            vec![4, 5, 6, 7, 8, 9, 1, 2, 3],
+            vec![7, 8, 9, 1, 2, 3, 4, 5, 6],
+            vec![2, 3, 4, 5, 6, 7, 8, 9, 1],
+            vec![5, 6, 7, 8, 9, 1, 2, 3, 4],
+            vec![8, 9, 1, 2, 3, 4, 5, 6, 7],
+            vec![3, 4, 5, 6, 7, 8, 9, 1, 2],
+            vec![6, 7, 8, 9, 1, 2, 3, 4, 5],
+            vec![9, 1, 2, 3, 4, 5, 6, 7, 8],
+        ];
+        assert!(run::<true, false>(&blank, &anti));
+        assert!(run::<false, true>(&blank, &anti));
+        assert!(run::<true, true>(&blank, &anti));
+
+        // the example is a classic sudoku, but repeats 9 a knight's move
+        // apart at (0, 7) and (1, 5) and 6 a king's move apart at (0, 3) and
+        // (1, 2)
+        let (public_grid, solution) = example_grids();
+        assert!(run::<false, false>(&public_grid, &solution));
+        assert!(!run::<true, false>(&public_grid, &solution));
+        assert!(!run::<false, true>(&public_grid, &solution));
+    }
+
+    /// How a variant under test extends the plain 9x9 config.
+    type Configure = fn(&mut ConstraintSystem<Fp>, &mut SudokuConfig);
+
+    /// A variant under test, e.g. a set of cages or dots.
+    trait Variant {
+        const CONFIGURE: Configure;
+    }
+
+    /// The sudoku gates plus those `V` adds.
+    struct VariantCircuit<V> {
+        solution: Vec<Vec<Fp>>,
+        _variant: PhantomData<V>,
+    }
+
+    impl<V: Variant> Circuit<Fp> for VariantCircuit<V> {
+        type Config = SudokuConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                solution: Vec::new(),
+                _variant: PhantomData,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let mut config = SudokuChip::configure(meta);
+            V::CONFIGURE(meta, &mut config);
+            config
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            SudokuChip::construct(config).assign(layouter, &self.solution)?;
+            Ok(())
+        }
+    }
+
+    /// Whether the example witness satisfies the sudoku gates and `V`'s.
+    fn run_variant<V: Variant>() -> bool {
+        let (public_grid, solution) = example_grids();
+        let circuit = VariantCircuit::<V> {
+            solution: u64_grid_to_fp_grid(solution),
+            _variant: PhantomData,
+        };
+        let prover = MockProver::run(7, &circuit, packed(public_grid)).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn killer_cages() {
+        // 5 + 7 along row 0, and an L of 9, 7 and 6 across two columns whose
+        // last two cells are givens
+        struct Valid;
+        impl Variant for Valid {
+            const CONFIGURE: Configure = |meta, config| {
+                let cages = [
+                    (12, vec![(0, 0), (0, 1)]),
+                    (22, vec![(4, 4), (4, 5), (5, 4)]),
+                ];
+                SudokuChip::configure_cages(meta, config, &cages)
+            };
+        }
+        assert!(run_variant::<Valid>());
+
+        struct OffByOne;
+        impl Variant for OffByOne {
+            const CONFIGURE: Configure = |meta, config| {
+                let cages = [
+                    (12, vec![(0, 0), (0, 1)]),
+                    (23, vec![(4, 4), (4, 5), (5, 4)]),
+                ];
+                SudokuChip::configure_cages(meta, config, &cages)
+            };
+        }
+        assert!(!run_variant::<OffByOne>());
+    }
+
+    /// Applies `grid_mutation` to the example's completed grid, witnesses
+    /// every cell of the result privately against a blank puzzle and asserts
+    /// MockProver rejects it.
+    fn expect_rejected(grid_mutation: impl Fn(&mut [[u8; 9]; 9])) {
+        let (clues, witness) = example_arrays();
+        let mut grid = merge_grid(&clues, &witness);
+        grid_mutation(&mut grid);
+
+        let circuit = MyCircuit::<Fp>::new(Grid(grid).into()).unwrap();
+        let prover = MockProver::run(7, &circuit, packed(vec![vec![0; 9]; 9])).unwrap();
+        assert!(prover.verify().is_err(), "accepted\n{:#}", Grid(grid));
+    }
+
+    #[test]
+    fn solution_hash_is_salted() {
+        let (clues, witness) = example_arrays();
+        let full = merge_grid(&clues, &witness);
+        // relabelling 1 and 2 gives another valid grid
+        let other = full.map(|row| row.map(|v| [0, 2, 1, 3, 4, 5, 6, 7, 8, 9][v as usize]));
+        let salt = Fp::from(0x5a17);
+
+        let accepts = |grid: [[u8; 9]; 9], salt, hash| {
+            let circuit = HashedCircuit::new(Grid(grid).into(), salt).unwrap();
+            let instance = hashed_instance(packed(vec![vec![0; 9]; 9]), hash);
+            MockProver::run(12, &circuit, instance)
+                .unwrap()
+                .verify()
+                .is_ok()
+        };
+
+        // the same grid, witnessed against the puzzle's givens, hashes the same
+        let (puzzle, witness) = example_grids();
+        let circuit = HashedCircuit::new(u64_grid_to_fp_grid(witness), salt).unwrap();
+        let instance = hashed_instance(packed(puzzle), solution_hash(&full, salt));
+        MockProver::run(12, &circuit, instance)
+            .unwrap()
+            .assert_satisfied();
+        assert_ne!(solution_hash(&full, salt), solution_hash(&other, salt));
+        assert!(accepts(full, salt, solution_hash(&full, salt)));
+        assert!(accepts(other, salt, solution_hash(&other, salt)));
+        assert!(!accepts(other, salt, solution_hash(&full, salt)));
+
+        // the hash depends on the salt, so the clues alone do not give it away
+        let unsalted = Fp::zero();
+        assert_ne!(solution_hash(&full, salt), solution_hash(&full, unsalted));
+        assert!(!accepts(full, unsalted, solution_hash(&full, salt)));
+    }
+
+    #[test]
+    fn rejects_negative_vectors() {
+        // swapping (0, 0) and (2, 0) keeps column 0 and box 0 valid
+        expect_rejected(|g| {
+            let (a, b) = (g[0][0], g[2][0]);
+            g[0][0] = b;
+            g[2][0] = a;
+        });
+        // swapping (0, 0) and (0, 1) keeps row 0 and box 0 valid
+        expect_rejected(|g| g[0].swap(0, 1));
+        // swapping rows 2 and 3 keeps every row and column valid
+        expect_rejected(|g| g.swap(2, 3));
+        expect_rejected(|g| g[0][0] = 0);
+        expect_rejected(|g| g[0][0] = 10);
+
+        // the harness leaves the instance blank; this one edits a clue
+        let (mut puzzle, solution) = example_grids();
+        puzzle[0][2] = 2;
+        let circuit = MyCircuit::<Fp>::new(u64_grid_to_fp_grid(solution)).unwrap();
+        let prover = MockProver::run(7, &circuit, packed(puzzle)).unwrap();
+        assert!(
+            prover.verify().is_err(),
+            "clue disagreeing with the instance was accepted"
+        );
+    }
+
+    /// Synthesizes with [`SudokuChip::assign_full`].
+    struct FullGridCircuit {
+        grid: Vec<Vec<Fp>>,
+        clues: Vec<Vec<bool>>,
+    }
+
+    impl Circuit<Fp> for FullGridCircuit {
+        type Config = SudokuConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                grid: Vec::new(),
+                clues: Vec::new(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            SudokuChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            SudokuChip::construct(config).assign_full(layouter, &self.grid, &self.clues)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn full_grid_with_clue_mask() {
+        let (public_grid, solution) = example_grids();
+        let full = example_full();
+        let clues: Vec<Vec<bool>> = public_grid
+            .iter()
+            .map(|row| row.iter().map(|&given| given != 0).collect())
+            .collect();
+
+        let run_full = |puzzle: Vec<Vec<u64>>, grid: Vec<Vec<u64>>| {
+            let circuit = FullGridCircuit {
+                grid: u64_grid_to_fp_grid(grid),
+                clues: clues.clone(),
+            };
+            MockProver::run(7, &circuit, packed(puzzle))
+                .unwrap()
+                .verify()
+                .is_ok()
+        };
+        let run_sentinel = |puzzle: Vec<Vec<u64>>, solution: Vec<Vec<u64>>| {
+            let circuit = MyCircuit::<Fp>::new(u64_grid_to_fp_grid(solution)).unwrap();
+            MockProver::run(7, &circuit, packed(puzzle))
+                .unwrap()
+                .verify()
+                .is_ok()
+        };
+
+        assert!(run_full(public_grid.clone(), full.clone()));
+        assert!(run_sentinel(public_grid.clone(), solution.clone()));
+
+        // a clue that disagrees with the instance fails both ways
+        let mut moved = public_grid.clone();
+        moved[0][2] = 2;
+        assert!(!run_full(moved.clone(), full.clone()));
+        assert!(!run_sentinel(moved, solution));
+
+        // so does a grid cell that disagrees with its clue
+        let mut wrong = full;
+        wrong[0][2] = 2;
+        assert!(!run_full(public_grid, wrong));
+    }
+
+    /// Synthesizes with [`SudokuChip::assign_with_clues`].
+    struct CluesCircuit {
+        solution: Vec<Vec<Fp>>,
+    }
+
+    impl Circuit<Fp> for CluesCircuit {
+        type Config = SudokuConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                solution: Vec::new(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            SudokuChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            SudokuChip::construct(config).assign_with_clues(layouter, &self.solution)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn completion_is_bound_to_its_clues() {
+        let (public_grid, _) = example_grids();
+        let full = example_full();
+        let run = |completion: Vec<Vec<u64>>| {
+            let circuit = CluesCircuit {
+                solution: u64_grid_to_fp_grid(completion),
+            };
+            MockProver::run(7, &circuit, packed(public_grid.clone()))
+                .unwrap()
+                .verify()
+                .is_ok()
+        };
+
+        assert!(run(full.clone()));
+
+        // swapping the digits 1 and 2 everywhere leaves a valid sudoku, but
+        // one that overwrites the clue 1 at (0, 2)
+        let relabeled: Vec<Vec<u64>> = full
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&value| match value {
+                        1 => 2,
+                        2 => 1,
+                        other => other,
+                    })
+                    .collect()
+            })
+            .collect();
+        assert!(!run(relabeled));
+
+        let mut overwritten = full;
+        overwritten[0][2] = 2;
+        assert!(!run(overwritten));
+    }
+
+    #[test]
+    fn rows_and_columns_are_not_transposed() {
+        let full = example_full();
+        let transpose = |grid: &Vec<Vec<u64>>| -> Vec<Vec<u64>> {
+            (0..9)
+                .map(|col| grid.iter().map(|row| row[col]).collect())
+                .collect()
+        };
+        let report = |grid: Vec<Vec<u64>>| {
+            let circuit = MyCircuit::<Fp>::new(u64_grid_to_fp_grid(grid)).unwrap();
+            diagnose(7, &circuit, packed(vec![vec![0; 9]; 9]), 9).unwrap()
+        };
+
+        assert!(report(full.clone()).is_empty());
+        assert!(report(transpose(&full)).is_empty());
+
+        // swapping (0, 0) and (2, 0) keeps every column and box valid but
+        // breaks rows 0 and 2, and only those
+        let mut by_columns = full;
+        by_columns[0][0] = 3;
+        by_columns[2][0] = 5;
+        let failures = report(by_columns.clone());
+        assert!(!failures.is_empty());
+        for failure in &failures {
+            assert_eq!(failure.gate, "rows");
+            assert!(matches!(
+                failure.location,
+                Some(GridLocation::Row(0)) | Some(GridLocation::Row(2))
+            ));
+        }
+
+        // the transpose fails in grid columns 0 and 2 instead
+        let failures = report(transpose(&by_columns));
+        assert!(!failures.is_empty());
+        for failure in &failures {
+            assert_eq!(failure.gate, "columns");
+            assert!(matches!(
+                failure.location,
+                Some(GridLocation::Column(0)) | Some(GridLocation::Column(2))
+            ));
+        }
+    }
+
+    /// A valid completed grid: a base pattern with its digits relabelled and
+    /// its bands, stacks, and the rows and columns within them shuffled, then
+    /// possibly transposed.
+    fn valid_grid() -> impl Strategy<Value = Vec<Vec<u64>>> {
+        let order = || Just(vec![0, 1, 2]).prop_shuffle();
+        let lines = move || (order(), [order(), order(), order()]);
+        (
+            Just((1..=9).collect::<Vec<u64>>()).prop_shuffle(),
+            lines(),
+            lines(),
+            any::<bool>(),
+        )
+            .prop_map(|(digits, (bands, rows), (stacks, cols), transpose)| {
+                let row = |i: usize| bands[i / 3] * 3 + rows[i / 3][i % 3];
+                let col = |j: usize| stacks[j / 3] * 3 + cols[j / 3][j % 3];
+                let base = |r: usize, c: usize| digits[(r * 3 + r / 3 + c) % 9];
+                (0..9)
+                    .map(|i| {
+                        (0..9)
+                            .map(|j| {
+                                if transpose {
+                                    base(row(j), col(i))
+                                } else {
+                                    base(row(i), col(j))
+                                }
+                            })
+                            .collect()
+                    })
+                    .collect()
+            })
+    }
+
+    /// Splits `grid` into the puzzle and the witness, with the cells `given`
+    /// marks as clues.
+    fn split(grid: &[Vec<u64>], given: &[bool]) -> (Vec<Vec<u64>>, Vec<Vec<u64>>) {
+        let pick = |keep: bool| {
+            grid.iter()
+                .enumerate()
+                .map(|(r, row)| {
+                    row.iter()
+                        .enumerate()
+                        .map(|(c, &v)| if given[r * 9 + c] == keep { v } else { 0 })
+                        .collect()
+                })
+                .collect()
+        };
+        (pick(true), pick(false))
+    }
+
+    fn accepts(grid: &[Vec<u64>], given: &[bool]) -> bool {
+        let (puzzle, witness) = split(grid, given);
+        let circuit = MyCircuit::<Fp>::new(u64_grid_to_fp_grid(witness)).unwrap();
+        MockProver::run(7, &circuit, packed(puzzle))
+            .unwrap()
+            .verify()
+            .is_ok()
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn accepts_random_valid_boards(
+            grid in valid_grid(),
+            given in prop::collection::vec(any::<bool>(), 81),
+        ) {
+            prop_assert!(accepts(&grid, &given));
+        }
+
+        #[test]
+        fn rejects_one_changed_cell(
+            mut grid in valid_grid(),
+            given in prop::collection::vec(any::<bool>(), 81),
+            cell in 0..81usize,
+            shift in 1..9u64,
+        ) {
+            // any other digit repeats one already in the cell's row
+            let value = &mut grid[cell / 9][cell % 9];
+            *value = (*value - 1 + shift) % 9 + 1;
+            prop_assert!(!accepts(&grid, &given));
+        }
+    }
+
+    #[test]
+    fn digit_targets() {
+        assert_eq!(SudokuChip::<Fp>::digit_sum(), Fp::from(45));
+        assert_eq!(SudokuChip::<Fp>::digit_product(), Fp::from(362880));
+        assert_eq!(SudokuChip::<Fp>::power_sum(2), Fp::from(285));
+        assert_eq!(SudokuChip::<Fp, 4, 2>::digit_sum(), Fp::from(10));
+        assert_eq!(SudokuChip::<Fp, 4, 2>::digit_product(), Fp::from(24));
+    }
+
+    #[test]
+    fn committed_randomness() {
+        // the three Poseidon regions need more rows than the sudoku alone
+        let k = 9;
+
+        let (clues, witness) = example_arrays();
+        let full = merge_grid(&clues, &witness);
+        // four puzzles with the same completion, each keeping every fourth
+        // cell as a clue
+        let mut puzzles = [full; COMMITTED_PUZZLES];
+        for (index, puzzle) in puzzles.iter_mut().enumerate() {
+            for cell in 0..81 {
+                if cell % COMMITTED_PUZZLES != index {
+                    puzzle[cell / 9][cell % 9] = 0;
+                }
+            }
+        }
+
+        // whether a proof for the table entry `index`, made with
+        // `randomness`, verifies against the public `instance`
+        let run = |index: usize, randomness: Fp, instance: Vec<Vec<Fp>>| {
+            let witness = solution_witness(&puzzles[index], &full);
+            let circuit = CommittedCircuit::new(puzzles, witness, randomness).unwrap();
+            MockProver::run(k, &circuit, instance)
+                .unwrap()
+                .verify()
+                .is_ok()
+        };
+
+        let randomness = Fp::from(0x5eed);
+        let index = puzzle_index(randomness);
+        let public_input = committed_instance(&puzzles, randomness);
+        assert!(run(index, randomness, public_input.clone()));
+
+        // randomness other than the committed one does not open the
+        // commitment, even where it selects the same puzzle
+        let swapped = (1..)
+            .map(|offset| randomness + Fp::from(offset))
+            .find(|&other| puzzle_index(other) == index)
+            .unwrap();
+        assert!(!run(index, swapped, public_input.clone()));
+
+        // and a server that hands out another puzzle of the table under the
+        // same commitment is caught by the selection
+        let other = (index + 1) % COMMITTED_PUZZLES;
+        let mut handed_out = vec![Vec::<Vec<Fp>>::from(Grid(puzzles[other])).concat()];
+        handed_out.push(public_input[1].clone());
+        assert!(!run(other, randomness, handed_out));
+
+        assert_ne!(
+            randomness_commitment(randomness),
+            randomness_commitment(swapped)
+        );
+        let indices: Vec<_> = (0..32).map(|r| puzzle_index(Fp::from(r))).collect();
+        assert!((0..COMMITTED_PUZZLES).all(|index| indices.contains(&index)));
+    }
+
+    #[test]
+    fn kropki_dots() {
+        // (1,0) = 4 and (2,0) = 3 are consecutive; (5,0) = 2 and (5,1) = 4
+        // are in a 2:1 ratio.
+        struct Valid;
+        impl Variant for Valid {
+            const CONFIGURE: Configure = |meta, config| {
+                SudokuChip::configure_kropki(meta, config, &[((1, 0), (2, 0))], &[((5, 0), (5, 1))])
+            };
+        }
+
+        // (0,0) = 5 and (0,1) = 7 are neither.
+        struct BadWhite;
+        impl Variant for BadWhite {
+            const CONFIGURE: Configure =
+                |meta, config| SudokuChip::configure_kropki(meta, config, &[((0, 0), (0, 1))], &[]);
+        }
+
+        struct BadBlack;
+        impl Variant for BadBlack {
+            const CONFIGURE: Configure =
+                |meta, config| SudokuChip::configure_kropki(meta, config, &[], &[((0, 0), (0, 1))]);
+        }
+
+        assert!(run_variant::<Valid>());
+        assert!(!run_variant::<BadWhite>());
+        assert!(!run_variant::<BadBlack>());
+    }
+
+    #[test]
+    fn circuit_version() {
+        let mut meta = ConstraintSystem::<Fp>::default();
+        let mut config: SudokuConfig = SudokuChip::configure(&mut meta);
+        let versions =
+            |config: &SudokuConfig| config.versions().iter().copied().collect::<Vec<_>>();
+        assert_eq!(versions(&config), [CircuitVersion::Classic]);
+
+        // no gate, no variant
+        SudokuChip::configure_parity(&mut meta, &mut config, &[], &[]);
+        SudokuChip::configure_cages(&mut meta, &mut config, &[]);
+        assert_eq!(versions(&config), [CircuitVersion::Classic]);
+
+        // variants combine instead of overwriting each other
+        SudokuChip::configure_kropki(&mut meta, &mut config, &[((1, 0), (2, 0))], &[]);
+        SudokuChip::configure_diagonals(&mut meta, &mut config);
+        assert_eq!(
+            versions(&config),
+            [
+                CircuitVersion::Classic,
+                CircuitVersion::Kropki,
+                CircuitVersion::Diagonal
+            ]
+        );
+    }
+
+    #[test]
+    fn layout_info_matches_the_constraint_system() {
+        let mut meta = ConstraintSystem::<Fp>::default();
+        let mut config: SudokuConfig = SudokuChip::configure(&mut meta);
+        let info = config.layout_info();
+        // nine grid columns and nine for the copied givens
+        assert_eq!(
+            info,
+            LayoutInfo {
+                advice_columns: 18,
+                instance_columns: 1,
+                rows_used: 9,
+                selectors: 2,
+            }
+        );
+        assert_eq!(info.advice_columns, meta.num_advice_columns());
+        assert_eq!(info.instance_columns, meta.num_instance_columns());
+        assert_eq!(info.selectors, meta.num_selectors());
+
+        SudokuChip::configure_anti_moves(&mut meta, &mut config, false, true);
+        assert_eq!(
+            config.layout_info().advice_columns,
+            meta.num_advice_columns()
+        );
+    }
+
+    struct AggregatesCircuit {
+        solution: Vec<Vec<Fp>>,
+    }
+
+    impl Circuit<Fp> for AggregatesCircuit {
+        type Config = SudokuConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                solution: Vec::new(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let config = SudokuChip::configure(meta);
+            SudokuChip::configure_aggregates(
+                meta,
+                &config,
+                &[LineAggregate::RowSums, LineAggregate::ColumnSums],
+            );
+            config
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            SudokuChip::construct(config).assign(layouter, &self.solution)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn revealed_aggregates() {
+        let (public_grid, solution) = example_grids();
+        let circuit = AggregatesCircuit {
+            solution: u64_grid_to_fp_grid(solution),
+        };
+
+        let mut public_input = packed(public_grid);
+        public_input.push(vec![Fp::from(45); 18]);
+        let prover = MockProver::run(7, &circuit, public_input.clone()).unwrap();
+        prover.assert_satisfied();
+
+        public_input[1][10] += Fp::one();
+        let prover = MockProver::run(7, &circuit, public_input).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn second_puzzle_is_accepted() {
+        // a 17-clue puzzle whose solution does not start with 5, 7
+        let public_grid = vec![
+            vec![0, 0, 0, 0, 0, 0, 0, 1, 0],
+            vec![4, 0, 0, 0, 0, 0, 0, 0, 0],
+            vec![0, 2, 0, 0, 0, 0, 0, 0, 0],
+            vec![0, 0, 0, 0, 5, 0, 4, 0, 7],
+            vec![0, 0, 8, 0, 0, 0, 3, 0, 0],
+            vec![0, 0, 1, 0, 9, 0, 0, 0, 0],
+            vec![3, 0, 0, 4, 0, 0, 2, 0, 0],
+            vec![0, 5, 0, 1, 0, 0, 0, 0, 0],
+            vec![0, 0, 0, 8, 0, 6, 0, 0, 0],
+        ];
+
+        let solution = vec![
+            vec![6, 9, 3, 7, 8, 4, 5, 0, 2],
+            vec![0, 8, 7, 5, 1, 2, 9, 3, 6],
+            vec![1, 0, 5, 9, 6, 3, 8, 7, 4],
+            vec![9, 3, 2, 6, 0, 1, 0, 8, 0],
+            vec![5, 6, 0, 2, 4, 7, 0, 9, 1],
+            vec![7, 4, 0, 3, 0, 8, 6, 2, 5],
+            vec![0, 1, 9, 0, 7, 5, 0, 6, 8],
+            vec![8, 0, 6, 0, 2, 9, 7, 4, 3],
+            vec![2, 7, 4, 0, 3, 0, 1, 5, 9],
+        ];
+
+        let circuit = MyCircuit::<Fp> {
+            solution: u64_grid_to_fp_grid(solution),
+            chunked: false,
+        };
+        let prover = MockProver::run(7, &circuit, packed(public_grid)).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn unrelated_grid_is_rejected() {
+        let (public_grid, _) = example_grids();
+
+        // A valid completed grid (the example's solution with 1 and 2
+        // swapped) supplied entirely as private witness, so no cell is copied
+        // from the instance. It contradicts the given 1 at (0, 2).
+        let unrelated = vec![
+            vec![5, 7, 2, 6, 1, 4, 3, 9, 8],
+            vec![4, 1, 6, 3, 8, 9, 2, 5, 7],
+            vec![3, 8, 9, 7, 5, 2, 1, 6, 4],
+            vec![9, 5, 7, 4, 2, 1, 8, 3, 6],
+            vec![2, 6, 3, 8, 9, 7, 5, 4, 1],
+            vec![1, 4, 8, 5, 6, 3, 9, 7, 2],
+            vec![8, 9, 4, 1, 7, 5, 6, 2, 3],
+            vec![6, 3, 5, 2, 4, 8, 7, 1, 9],
+            vec![7, 2, 1, 9, 3, 6, 4, 8, 5],
+        ];
+
+        let circuit = MyCircuit::<Fp> {
+            solution: u64_grid_to_fp_grid(unrelated),
+            chunked: false,
+        };
+        let prover = MockProver::run(7, &circuit, packed(public_grid)).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn circuit_commitment_tracks_gates() {
+        struct Valid;
+        impl Variant for Valid {
+            const CONFIGURE: Configure =
+                |meta, config| SudokuChip::configure_kropki(meta, config, &[((1, 0), (2, 0))], &[]);
+        }
+
+        struct Moved;
+        impl Variant for Moved {
+            const CONFIGURE: Configure =
+                |meta, config| SudokuChip::configure_kropki(meta, config, &[((1, 0), (1, 1))], &[]);
+        }
+
+        let classic = circuit_commitment::<Fp, MyCircuit<Fp>>();
+        assert_eq!(classic, circuit_commitment::<Fp, MyCircuit<Fp>>());
+
+        let kropki = circuit_commitment::<Fp, VariantCircuit<Valid>>();
+        assert_ne!(classic, kropki);
+        assert_ne!(kropki, circuit_commitment::<Fp, VariantCircuit<Moved>>());
+        assert_ne!(classic, circuit_commitment::<Fp, AggregatesCircuit>());
+    }
+
+    #[test]
+    fn chunked_synthesis_matches_single_region() {
+        let (public_grid, solution) = example_grids();
+        let public_input = packed(public_grid);
+
+        let mut tampered = public_input.clone();
+        tampered[0][0] += Fp::one();
+
+        for (public_input, expected) in [(public_input, true), (tampered, false)] {
+            for chunked in [false, true] {
+                let circuit = MyCircuit::<Fp> {
+                    solution: u64_grid_to_fp_grid(solution.clone()),
+                    chunked,
+                };
+                let prover = MockProver::run(7, &circuit, public_input.clone()).unwrap();
+                assert_eq!(prover.verify().is_ok(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn from_clues_solves_the_example() {
+        let (public_grid, _) = example_grids();
+        let (clues, _) = example_arrays();
+
+        let circuit = MyCircuit::<Fp>::from_clues(&clues).unwrap();
+        let prover = MockProver::run(7, &circuit, packed(public_grid)).unwrap();
+        prover.assert_satisfied();
+
+        // (0, 2) already holds the 1 in row 0
+        clues[0][0] = 1;
+        assert!(matches!(
+            MyCircuit::<Fp>::from_clues(&clues),
+            Err(SudokuError::Unsolvable)
+        ));
+    }
+
+    #[test]
+    fn for_puzzle_requires_disjoint_cells() {
+        let (public_grid, solution) = example_grids();
+        let puzzle = u64_grid_to_fp_grid(public_grid);
+        let check = |solution: Vec<Vec<u64>>| {
+            MyCircuit::<Fp>::for_puzzle(u64_grid_to_fp_grid(solution), &puzzle)
+        };
+        assert!(check(solution.clone()).is_ok());
+
+        // (0, 0) is blank in the puzzle, so leaving it out leaves it free
+        let mut missing = solution.clone();
+        missing[0][0] = 0;
+        assert!(matches!(
+            check(missing),
+            Err(SudokuError::Incomplete { row: 0, col: 0 })
+        ));
+
+        // (0, 2) is the given 1
+        let mut overlapping = solution.clone();
+        overlapping[0][2] = 1;
+        assert!(matches!(
+            check(overlapping),
+            Err(SudokuError::ClueOverlap { row: 0, col: 2 })
+        ));
+
+        assert!(matches!(
+            MyCircuit::<Fp>::for_puzzle(u64_grid_to_fp_grid(solution), &puzzle[..8]),
+            Err(SudokuError::BadDimensions { rows: 8, cols: 9 })
+        ));
+    }
+
+    #[test]
+    fn constructors_check_dimensions() {
+        let (_, solution) = example_grids();
+        assert!(MyCircuit::<Fp>::new(u64_grid_to_fp_grid(solution.clone())).is_ok());
+
+        let short = u64_grid_to_fp_grid(solution[..8].to_vec());
+        assert!(matches!(
+            MyCircuit::<Fp>::new(short),
+            Err(SudokuError::BadDimensions { rows: 8, cols: 9 })
+        ));
+
+        let mut ragged = solution.clone();
+        ragged[3].pop();
+        assert!(matches!(
+            MyCircuit::<Fp>::new(u64_grid_to_fp_grid(ragged)),
+            Err(SudokuError::BadDimensions { rows: 9, cols: 8 })
+        ));
+
+        assert!(matches!(
+            MyCircuit::<Fp, 4, 2>::sized(u64_grid_to_fp_grid(solution)),
+            Err(SudokuError::BadDimensions { rows: 9, cols: 9 })
+        ));
+    }
+
+    #[derive(Clone)]
+    struct NotEqualCircuit {
+        a: Fp,
+        b: Fp,
+    }
+
+    impl Circuit<Fp> for NotEqualCircuit {
+        type Config = (NotEqualConfig, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let cells = meta.advice_column();
+            meta.enable_equality(cells);
+            (NotEqualConfig::configure(meta), cells)
+        }
+
+        fn synthesize(
+            &self,
+            (not_equal, cells): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "pair",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", cells, 0, || Value::known(self.a))?;
+                    let b = region.assign_advice(|| "b", cells, 1, || Value::known(self.b))?;
+                    not_equal.assert_ne(&mut region, 0, &a, &b)
+                },
+            )
+        }
+    }
+
+    struct EntriesCircuit {
+        entries: Vec<Vec<Entry<Fp>>>,
+    }
+
+    impl Circuit<Fp> for EntriesCircuit {
+        type Config = SudokuConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                entries: self.entries.clone(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            SudokuChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            SudokuChip::construct(config).assign_entries(layouter, &self.entries)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn explicit_entries_do_not_overload_zero() {
+        let (public_grid, solution) = example_grids();
+        let solution = u64_grid_to_fp_grid(solution);
+        let run = |entries| {
+            MockProver::run(7, &EntriesCircuit { entries }, packed(public_grid.clone()))
+                .unwrap()
+                .verify()
+                .is_ok()
+        };
+
+        let entries = Entry::from_zero_blanks(&solution);
+        assert_eq!(entries[0][2], Entry::Given);
+        assert!(run(entries.clone()));
+
+        // (0, 2) is the clue 1: left as a given it is copied in, but a
+        // filled zero there is a wrong digit, where the zero-at-clue
+        // encoding of the same grid would have copied the clue
+        let mut filled_zero = entries.clone();
+        filled_zero[0][2] = Entry::Filled(Fp::zero());
+        assert!(!run(filled_zero));
+
+        // filling a clue with its own digit is fine
+        let mut filled_clue = entries;
+        filled_clue[0][2] = Entry::Filled(Fp::one());
+        assert!(run(filled_clue));
+    }
+
+    /// Lays out `OFFSET` rows of another gadget's cells, then the grid, in one
+    /// region.
+    struct OffsetCircuit<const OFFSET: usize> {
+        solution: Vec<Vec<Fp>>,
+    }
+
+    impl<const OFFSET: usize> Circuit<Fp> for OffsetCircuit<OFFSET> {
+        type Config = (SudokuConfig, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                solution: self.solution.clone(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            (SudokuChip::configure(meta), meta.advice_column())
+        }
+
+        fn synthesize(
+            &self,
+            (config, other): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = SudokuChip::construct(config);
+            chip.load_table(&mut layouter)?;
+            layouter.assign_region(
+                || "shared",
+                |mut region| {
+                    for row in 0..OFFSET {
+                        region.assign_advice(
+                            || "other gadget",
+                            other,
+                            row,
+                            || Value::known(Fp::from(row as u64)),
+                        )?;
+                    }
+                    chip.assign_at(&mut region, OFFSET, &self.solution)
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn grid_at_an_offset_in_a_shared_region() {
+        let (public_grid, solution) = example_grids();
+        let run = |solution: Vec<Vec<u64>>| {
+            let circuit = OffsetCircuit::<3> {
+                solution: u64_grid_to_fp_grid(solution),
+            };
+            MockProver::run(7, &circuit, packed(public_grid.clone()))
+                .unwrap()
+                .verify()
+                .is_ok()
+        };
+
+        assert!(run(solution.clone()));
+        let mut duplicate = solution;
+        duplicate[0].swap(0, 1);
+        assert!(!run(duplicate));
+    }
+
+    /// The classic chip with its grid packed into `COLUMNS` advice columns.
+    #[derive(Default)]
+    struct PackedCircuit<const COLUMNS: usize> {
+        solution: Vec<Vec<Fp>>,
+    }
+
+    impl<const COLUMNS: usize> Circuit<Fp> for PackedCircuit<COLUMNS> {
+        type Config = SudokuConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            SudokuChip::configure_with_layout(meta, Layout::Packed { columns: COLUMNS })
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            SudokuChip::construct(config).assign(layouter, &self.solution)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn packed_layouts() {
+        fn check<const COLUMNS: usize>(advice_columns: usize, rows_used: usize) {
+            let mut meta = ConstraintSystem::<Fp>::default();
+            let info = PackedCircuit::<COLUMNS>::configure(&mut meta).layout_info();
+            assert_eq!(info.advice_columns, advice_columns);
+            assert_eq!(info.advice_columns, meta.num_advice_columns());
+            assert_eq!(info.rows_used, rows_used);
+
+            let (public_grid, solution) = example_grids();
+            let run = |solution: Vec<Vec<u64>>| {
+                let circuit = PackedCircuit::<COLUMNS> {
+                    solution: u64_grid_to_fp_grid(solution),
+                };
+                MockProver::run(7, &circuit, packed(public_grid.clone()))
+                    .unwrap()
+                    .verify()
+                    .is_ok()
+            };
+            assert!(run(solution.clone()));
+
+            // two cells of a row swapped: the columns and boxes gates fail
+            let mut swapped = solution.clone();
+            swapped[0].swap(0, 1);
+            assert!(!run(swapped));
+
+            // a clue overwritten in the witness: the givens gate fails
+            let mut overwritten = solution.clone();
+            overwritten[0][2] = 3;
+            assert!(!run(overwritten));
+
+            // a blank left in the grid: the range check fails
+            let mut blank = solution;
+            blank[8][8] = 0;
+            assert!(!run(blank));
+        }
+
+        // the grid and its copied givens, one column each, 81 rows
+        check::<1>(2, 81);
+        check::<3>(6, 27);
+
+        assert_eq!(Layout::Packed { columns: 3 }.place(9, (4, 5)), (2, 13));
+        assert_eq!(Layout::Wide.place(9, (4, 5)), (4, 5));
+    }
+
+    #[test]
+    fn not_equal_gadget() {
+        let run = |a: u64, b: u64| {
+            let circuit = NotEqualCircuit {
+                a: Fp::from(a),
+                b: Fp::from(b),
+            };
+            MockProver::run(4, &circuit, vec![])
+                .unwrap()
+                .verify()
+                .is_ok()
+        };
+        assert!(run(3, 7));
+        assert!(run(0, 1));
+        assert!(!run(5, 5));
+        assert!(!run(0, 0));
+    }
+
+    #[test]
+    fn partial_grids() {
+        let run = |puzzle: Vec<Vec<u64>>, grid: Vec<Vec<Option<u64>>>| {
+            let grid = grid
+                .into_iter()
+                .map(|row| row.into_iter().map(|cell| cell.map(Fp::from)).collect())
+                .collect();
+            let circuit = PartialCircuit::new(grid).unwrap();
+            MockProver::run(7, &circuit, packed(puzzle))
+                .unwrap()
+                .verify()
+                .is_ok()
+        };
+        let blank = vec![vec![0; 9]; 9];
+        let empty = vec![vec![None; 9]; 9];
+
+        assert!(run(blank.clone(), empty.clone()));
+
+        // the example's clues plus the first two cells of its solution
+        let (public_grid, _) = example_grids();
+        let mut progress: Vec<Vec<Option<u64>>> = public_grid
+            .iter()
+            .map(|row| row.iter().map(|&c| (c != 0).then_some(c)).collect())
+            .collect();
+        progress[0][0] = Some(5);
+        progress[0][1] = Some(7);
+        assert!(run(public_grid.clone(), progress.clone()));
+
+        // a clue left empty
+        let mut missing_clue = progress.clone();
+        missing_clue[0][2] = None;
+        assert!(!run(public_grid.clone(), missing_clue));
+
+        // two filled 3s in row 0 (at (0, 1) and (0, 6)), nothing else placed
+        let mut repeated = empty.clone();
+        repeated[0][1] = Some(3);
+        repeated[0][6] = Some(3);
+        assert!(!run(blank.clone(), repeated));
+
+        // the same 3s in different rows, columns and boxes are fine
+        let mut apart = empty.clone();
+        apart[0][1] = Some(3);
+        apart[4][6] = Some(3);
+        assert!(run(blank.clone(), apart));
+
+        let mut out_of_range = empty;
+        out_of_range[8][8] = Some(10);
+        assert!(!run(blank, out_of_range));
+    }
+
+    #[test]
+    fn range_only_ignores_distinctness() {
+        let run = |grid: Vec<Vec<u64>>| {
+            let circuit = RangeOnlyCircuit::new(u64_grid_to_fp_grid(grid));
+            MockProver::run(5, &circuit, vec![]).unwrap().verify()
+        };
+
+        // every cell 5: far from a sudoku, but every digit is in range
+        assert!(run(vec![vec![5; 9]; 9]).is_ok());
+
+        let mut out_of_range = example_full();
+        out_of_range[4][4] = 10;
+        let failures = run(out_of_range).unwrap_err();
+        assert_eq!(
+            failure_report(&failures, 9),
+            vec![GateFailure {
+                gate: "range check".to_string(),
+                location: Some(GridLocation::Cell((4, 4))),
+                row: 4,
+            }]
+        );
+    }
+
+    #[test]
+    fn range_check_rejects_zero_and_ten() {
+        let (public_grid, solution) = example_grids();
+        // (0, 0) is blank in the puzzle
+        assert_eq!(public_grid[0][0], 0);
+
+        for value in [0, 10] {
+            let mut bad = solution.clone();
+            bad[0][0] = value;
+            let circuit = MyCircuit::new(u64_grid_to_fp_grid(bad)).unwrap();
+            let report = diagnose(7, &circuit, packed(public_grid.clone()), 9).unwrap();
+            assert!(report.iter().any(|failure| failure.gate == "range check"
+                && failure.location == Some(GridLocation::Cell((0, 0)))));
+        }
+    }
+
+    struct SumProductCircuit {
+        solution: Vec<Vec<Fp>>,
+    }
+
+    impl Circuit<Fp> for SumProductCircuit {
+        type Config = SudokuConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                solution: Vec::new(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            SudokuChip::configure_with_uniqueness(meta, UniquenessMode::SumProduct)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            SudokuChip::construct(config).assign(layouter, &self.solution)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn soundness_warning_tracks_uniqueness_mode() {
+        assert!(soundness_warning::<Fp, SumProductCircuit>().is_some());
+        assert!(soundness_warning::<Fp, MyCircuit<Fp>>().is_none());
+        assert!(soundness_warning::<Fp, BatchSudokuCircuit<Fp>>().is_none());
+        assert!(soundness_warning::<Fp, SamuraiCircuit<Fp>>().is_none());
+
+        let (public_grid, solution) = example_grids();
+        let circuit = SumProductCircuit {
+            solution: u64_grid_to_fp_grid(solution),
+        };
+        let prover = MockProver::run(7, &circuit, packed(public_grid)).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn duplicate_row_passing_sum_product_is_rejected() {
+        // Row 0 holds {1, 2, 4, 4, 4, 5, 7, 9, 9}: sum 45 and product 9!, so
+        // the sum/product check accepts it, but it is no permutation. The
+        // whole grid is private, against an all-blank puzzle.
+        let mut grid = vec![
+            vec![1, 2, 4, 4, 4, 5, 7, 9, 9],
+            vec![4, 2, 6, 3, 8, 9, 1, 5, 7],
+            vec![3, 8, 9, 7, 5, 1, 2, 6, 4],
+            vec![9, 5, 7, 4, 1, 2, 8, 3, 6],
+            vec![1, 6, 3, 8, 9, 7, 5, 4, 2],
+            vec![2, 4, 8, 5, 6, 3, 9, 7, 1],
+            vec![8, 9, 4, 2, 7, 5, 6, 1, 3],
+            vec![6, 3, 5, 1, 4, 8, 7, 2, 9],
+            vec![7, 1, 2, 9, 3, 6, 4, 8, 5],
+        ];
+        let blank = vec![vec![0; 9]; 9];
+
+        let row_0_fails = |report: Vec<GateFailure>| {
+            report
+                .iter()
+                .any(|failure| failure.location == Some(GridLocation::Row(0)))
+        };
+
+        let sum_product = SumProductCircuit {
+            solution: u64_grid_to_fp_grid(grid.clone()),
+        };
+        let report = diagnose(7, &sum_product, packed(blank.clone()), 9).unwrap();
+        assert!(!row_0_fails(report));
+
+        let circuit = MyCircuit::new(u64_grid_to_fp_grid(grid.clone())).unwrap();
+        let prover = MockProver::run(7, &circuit, packed(blank.clone())).unwrap();
+        assert!(prover.verify().is_err());
+        let report = diagnose(7, &circuit, packed(blank.clone()), 9).unwrap();
+        assert!(row_0_fails(report));
+
+        // the real row 0 passes both
+        grid[0] = vec![5, 7, 1, 6, 2, 4, 3, 9, 8];
+        let circuit = MyCircuit::new(u64_grid_to_fp_grid(grid)).unwrap();
+        let prover = MockProver::run(7, &circuit, packed(blank)).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn unit_gates_agree_on_a_duplicate() {
+        // (4, 4) should be 9; a 5 repeats the 5s at (4, 6), (2, 4) and
+        // (5, 3), so its row, column and box all break
+        let (public_grid, mut solution) = example_grids();
+        solution[4][4] = 5;
+        let solution = u64_grid_to_fp_grid(solution);
+
+        let permutation = MyCircuit::new(solution.clone()).unwrap();
+        let sum_product = SumProductCircuit { solution };
+        for report in [
+            diagnose(7, &permutation, packed(public_grid.clone()), 9).unwrap(),
+            diagnose(7, &sum_product, packed(public_grid.clone()), 9).unwrap(),
+        ] {
+            for unit in [
+                GridLocation::Row(4),
+                GridLocation::Column(4),
+                GridLocation::Box(4),
+            ] {
+                assert!(
+                    report.iter().any(|failure| failure.location == Some(unit)),
+                    "{:?} not reported in {:?}",
+                    unit,
+                    report
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn quadruple_clues() {
+        // the corner shared by the top-left four boxes: (2,2) = 9, (2,3) = 7,
+        // (3,2) = 7 and (3,3) = 4
+        const CORNER: Cell4 = [(2, 2), (2, 3), (3, 2), (3, 3)];
+
+        struct Present;
+        impl Variant for Present {
+            const CONFIGURE: Configure = |meta, config| {
+                SudokuChip::configure_quadruples(meta, config, &[(CORNER, vec![4, 7, 9])])
+            };
+        }
+
+        struct Missing;
+        impl Variant for Missing {
+            const CONFIGURE: Configure = |meta, config| {
+                SudokuChip::configure_quadruples(meta, config, &[(CORNER, vec![1, 4, 7])])
+            };
+        }
+
+        assert!(run_variant::<Present>());
+        assert!(!run_variant::<Missing>());
+    }
+
+    struct AlphabetCircuit {
+        solution: Vec<Vec<Fp>>,
+    }
+
+    impl Circuit<Fp> for AlphabetCircuit {
+        type Config = SudokuConfig<4, 2>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                solution: Vec::new(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            SudokuChip::configure_alphabet(meta, &[2, 3, 5, 7])
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            SudokuChip::construct(config).assign(layouter, &self.solution)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn four_digit_alphabet() {
+        let run = |puzzle: Vec<Vec<u64>>, solution: Vec<Vec<u64>>| {
+            let circuit = AlphabetCircuit {
+                solution: u64_grid_to_fp_grid(solution),
+            };
+            MockProver::run(5, &circuit, packed(puzzle))
+                .unwrap()
+                .verify()
+                .is_ok()
+        };
+
+        // 2 3 | 5 7
+        // 5 7 | 2 3
+        // ----+----
+        // 3 2 | 7 5
+        // 7 5 | 3 2
+        let puzzle = vec![
+            vec![2, 0, 0, 0],
+            vec![0, 0, 2, 0],
+            vec![0, 0, 0, 5],
+            vec![0, 5, 0, 0],
+        ];
+        let solution = vec![
+            vec![0, 3, 5, 7],
+            vec![5, 7, 0, 3],
+            vec![3, 2, 7, 0],
+            vec![7, 0, 3, 2],
+        ];
+        assert!(run(puzzle.clone(), solution.clone()));
+
+        // 4 is not in the alphabet
+        let mut outside = solution.clone();
+        outside[0][1] = 4;
+        assert!(!run(puzzle.clone(), outside));
+
+        // swapping two cells keeps row 1 distinct but repeats 7 and 5 in columns
+        let mut repeated = solution.clone();
+        repeated[1][0] = 7;
+        repeated[1][1] = 5;
+        assert!(!run(puzzle.clone(), repeated));
+
+        // a given the witness disagrees with
+        let mut contradicts = puzzle;
+        contradicts[0][1] = 2;
+        assert!(!run(contradicts, solution));
+    }
+
+    #[test]
+    fn four_by_four_board() {
+        let run = |puzzle: Vec<Vec<u64>>, solution: Vec<Vec<u64>>| {
+            let circuit = MyCircuit::<Fp, 4, 2>::sized(u64_grid_to_fp_grid(solution)).unwrap();
+            MockProver::run(5, &circuit, packed(puzzle))
+                .unwrap()
+                .verify()
+                .is_ok()
+        };
+
+        // 1 2 | 3 4
+        // 3 4 | 1 2
+        // ----+----
+        // 2 1 | 4 3
+        // 4 3 | 2 1
+        let puzzle = vec![
+            vec![1, 0, 0, 0],
+            vec![0, 4, 0, 0],
+            vec![0, 0, 4, 0],
+            vec![0, 0, 0, 1],
+        ];
+        let solution = vec![
+            vec![0, 2, 3, 4],
+            vec![3, 0, 1, 2],
+            vec![2, 1, 0, 3],
+            vec![4, 3, 2, 0],
+        ];
+        assert!(run(puzzle.clone(), solution.clone()));
+
+        // 5 is a digit on the classic board only
+        let mut outside = solution.clone();
+        outside[0][1] = 5;
+        assert!(!run(puzzle.clone(), outside));
+
+        let mut repeated = solution;
+        repeated[0][1] = 3;
+        assert!(!run(puzzle, repeated));
+    }
+
+    #[test]
+    fn six_by_six_board() {
+        let run = |puzzle: Vec<Vec<u64>>, solution: Vec<Vec<u64>>| {
+            let circuit = MyCircuit::<Fp, 6, 2, 3>::sized(u64_grid_to_fp_grid(solution)).unwrap();
+            diagnose::<Fp, _>(5, &circuit, packed(puzzle), 6).unwrap()
+        };
+
+        // 1 2 3 | 4 5 6
+        // 4 5 6 | 1 2 3
+        // ------+------
+        // 2 3 1 | 5 6 4
+        // 5 6 4 | 2 3 1
+        // ------+------
+        // 3 1 2 | 6 4 5
+        // 6 4 5 | 3 1 2
+        let full = vec![
+            vec![1, 2, 3, 4, 5, 6],
+            vec![4, 5, 6, 1, 2, 3],
+            vec![2, 3, 1, 5, 6, 4],
+            vec![5, 6, 4, 2, 3, 1],
+            vec![3, 1, 2, 6, 4, 5],
+            vec![6, 4, 5, 3, 1, 2],
+        ];
+        let mut puzzle = vec![vec![0; 6]; 6];
+        let mut solution = full;
+        for (row, col) in [(0, 0), (1, 4), (2, 2), (3, 5), (4, 1), (5, 3)] {
+            puzzle[row][col] = solution[row][col];
+            solution[row][col] = 0;
+        }
+        assert_eq!(run(puzzle, solution), vec![]);
+
+        // every row and column is a shift of 1..=6, but the top-left box
+        // holds 2 and 3 twice
+        let shifted: Vec<Vec<u64>> = (0..6)
+            .map(|row| (0..6).map(|col| (row + col) % 6 + 1).collect())
+            .collect();
+        let report = run(vec![vec![0; 6]; 6], shifted);
+        assert!(!report.is_empty());
+        assert!(report.iter().all(|f| f.gate == "boxes"), "{:?}", report);
+        // six power sums per box, so the last box's constraints are 30..36
+        assert!(report
+            .iter()
+            .any(|f| f.location == Some(GridLocation::Box(5))));
+    }
+
+    /// Restricts the blank (0, 0), which holds 5, to the pencil marks 2 and 5.
+    struct CandidatesCircuit {
+        solution: Vec<Vec<Fp>>,
+    }
+
+    impl Circuit<Fp> for CandidatesCircuit {
+        type Config = SudokuConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                solution: Vec::new(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let mut config = SudokuChip::configure(meta);
+            let candidates = HashMap::from([((0, 0), vec![2, 5])]);
+            SudokuChip::configure_candidates(meta, &mut config, &candidates);
+            config
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            SudokuChip::construct(config).assign(layouter, &self.solution)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn pencil_mark_candidates() {
+        let (public_grid, solution) = example_grids();
+        let report = |value| {
+            let mut solution = solution.clone();
+            solution[0][0] = value;
+            let circuit = CandidatesCircuit {
+                solution: u64_grid_to_fp_grid(solution),
+            };
+            diagnose(7, &circuit, packed(public_grid.clone()), 9).unwrap()
+        };
+
+        assert!(report(5).is_empty());
+        // 3 breaks the sudoku gates too, but only the candidates gate
+        // rules out 2
+        assert!(report(3).iter().any(|failure| failure.gate == "candidates"));
+        assert!(!report(2).iter().any(|failure| failure.gate == "candidates"));
+    }
+
+    #[test]
+    fn even_odd_cells() {
+        // In the example solution (2, 8) holds 4 and (2, 0) holds 3.
+        struct EvenHoldsFour;
+        impl Variant for EvenHoldsFour {
+            const CONFIGURE: Configure =
+                |meta, config| SudokuChip::configure_parity(meta, config, &[(2, 8)], &[(2, 0)]);
+        }
+
+        struct EvenHoldsThree;
+        impl Variant for EvenHoldsThree {
+            const CONFIGURE: Configure =
+                |meta, config| SudokuChip::configure_parity(meta, config, &[(2, 0)], &[]);
+        }
+
+        assert!(run_variant::<EvenHoldsFour>());
+        assert!(!run_variant::<EvenHoldsThree>());
+    }
+
+    /// The example's completed grid, [`merge_grid`] of its clues and witness.
+    fn example_full() -> Vec<Vec<u64>> {
+        let (clues, witness) = example_arrays();
+        to_vec_grid(&merge_grid(&clues, &witness))
+    }
+
+    /// The instance for puzzle `grid`: one column, row-major.
+    fn packed(grid: Vec<Vec<u64>>) -> Vec<Vec<Fp>> {
+        vec![u64_grid_to_fp_grid(grid).concat()]
+    }
+
+    fn u64_grid_to_fp_grid(sudoku: Vec<Vec<u64>>) -> Vec<Vec<Fp>> {
+        sudoku
+            .into_iter()
+            .map(|row| row.into_iter().map(Fp::from).collect())
+            .collect()
+    }
+}
@@ -0,0 +1,249 @@
+//! Maps MockProver failures back onto the sudoku grid so a failing witness
+//! can be explained in terms of rows, columns, boxes and cells.
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    dev::{CircuitCost, FailureLocation, MockProver, VerifyFailure},
+    pasta::{self, Fp},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+use crate::circuit::{Cell, MyCircuit};
+#[cfg(feature = "dev-graph")]
+use crate::error::SudokuError;
+#[cfg(feature = "dev-graph")]
+use halo2_proofs::dev::CircuitLayout;
+#[cfg(feature = "dev-graph")]
+use std::path::Path;
+
+/// The part of the grid a failed constraint talks about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridLocation {
+    Cell(Cell),
+    Row(usize),
+    Column(usize),
+    /// Boxes are numbered from 0, left to right, top to bottom.
+    Box(usize),
+}
+
+/// One failed gate constraint from a MockProver run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GateFailure {
+    /// Name the gate was created with, e.g. `"rows"`.
+    pub gate: String,
+    /// Grid location of the constraint, if the gate is one this crate knows.
+    pub location: Option<GridLocation>,
+    /// Row the gate's selector was enabled on, relative to its region.
+    pub row: usize,
+}
+
+/// Recovers the constraint index and gate name from the `Display` form
+/// `Constraint <index>[ ('<name>')] in gate <gate index> ('<gate name>')`.
+fn parse_constraint(constraint: &str) -> Option<(usize, String)> {
+    let index = constraint
+        .strip_prefix("Constraint ")?
+        .split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()?;
+    let (_, gate) = constraint.rsplit_once(" in gate ")?;
+    let name = gate.split_once("('")?.1.strip_suffix("')")?;
+    Some((index, name.to_string()))
+}
+
+/// Where on an `n` by `n` grid constraint `index` of `gate`, enabled at
+/// `row`, lies. This mirrors the loops in `SudokuChip::configure`, where grid
+/// row `i` is laid out down `advice[i]`.
+fn locate(gate: &str, index: usize, row: usize, n: usize) -> Option<GridLocation> {
+    match gate {
+        "givens" => Some(GridLocation::Cell((index / n, index % n))),
+        // One row of the region holds one grid column.
+        "columns" | "columns (sum/product)" => Some(GridLocation::Column(row)),
+        // One advice column holds one grid row; `n` power-sum constraints
+        // each, or two under `UniquenessMode::SumProduct`.
+        "rows" => Some(GridLocation::Row(index / n)),
+        "boxes" => Some(GridLocation::Box(index / n)),
+        "rows (sum/product)" => Some(GridLocation::Row(index / 2)),
+        "boxes (sum/product)" => Some(GridLocation::Box(index / 2)),
+        _ => None,
+    }
+}
+
+/// Lists every failed gate constraint in `failures` from a circuit over an
+/// `n` by `n` board, in the order MockProver
+/// reported them. Failed lookups are reported as `"range check"`: the crate's
+/// only lookups are the range check's, one per grid row, so lookup `i`
+/// failing at region offset `j` is cell `(i, j)`. Permutation failures are
+/// not tied to a gate and are skipped.
+pub fn failure_report(failures: &[VerifyFailure], n: usize) -> Vec<GateFailure> {
+    failures
+        .iter()
+        .filter_map(|failure| match failure {
+            VerifyFailure::ConstraintNotSatisfied {
+                constraint,
+                location,
+                ..
+            } => {
+                let (index, gate) = parse_constraint(&constraint.to_string())?;
+                let row = match location {
+                    FailureLocation::InRegion { offset, .. } => *offset,
+                    FailureLocation::OutsideRegion { row } => *row,
+                };
+                Some(GateFailure {
+                    location: locate(&gate, index, row, n),
+                    gate,
+                    row,
+                })
+            }
+            VerifyFailure::Lookup {
+                lookup_index,
+                location,
+            } => {
+                let row = match location {
+                    FailureLocation::InRegion { offset, .. } => *offset,
+                    FailureLocation::OutsideRegion { row } => *row,
+                };
+                Some(GateFailure {
+                    gate: "range check".to_string(),
+                    location: Some(GridLocation::Cell((*lookup_index, row))),
+                    row,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Runs MockProver and reports every failed gate constraint, located on an
+/// `n` by `n` board; an empty report means the witness satisfies all gates.
+pub fn diagnose<F: FieldExt, C: Circuit<F>>(
+    k: u32,
+    circuit: &C,
+    instance: Vec<Vec<F>>,
+    n: usize,
+) -> Result<Vec<GateFailure>, Error> {
+    let prover = MockProver::run(k, circuit, instance)?;
+    Ok(match prover.verify() {
+        Ok(()) => Vec::new(),
+        Err(failures) => failure_report(&failures, n),
+    })
+}
+
+/// A plain-text summary of the 9x9 circuit's size at `k`: column, selector,
+/// gate and lookup counts, the maximum gate degree, the rows reserved for
+/// blinding and the estimated size of a proof. Handy when choosing `k` or checking that a
+/// change to the gates made the circuit cheaper. Panics if the board does
+/// not fit in `2^k` rows.
+pub fn cost_report(k: u32) -> String {
+    let mut cs = ConstraintSystem::<Fp>::default();
+    MyCircuit::<Fp>::configure(&mut cs);
+
+    // any witness of the right shape lays out the same rows
+    let circuit = MyCircuit::new(vec![vec![Fp::one(); 9]; 9]).unwrap();
+    let cost = CircuitCost::<pasta::Eq, MyCircuit<Fp>>::measure(k as usize, &circuit);
+    let proof_size: usize = cost.proof_size(1).into();
+
+    format!(
+        "9x9 sudoku circuit at k = {}\n\
+         advice columns: {}\n\
+         instance columns: {}\n\
+         fixed columns: {}\n\
+         selectors: {}\n\
+         gates: {}\n\
+         lookups: {}\n\
+         max degree: {}\n\
+         reserved rows: {}\n\
+         estimated proof size: {} bytes\n",
+        k,
+        cs.num_advice_columns(),
+        cs.num_instance_columns(),
+        cs.num_fixed_columns(),
+        cs.num_selectors(),
+        cs.gates().len(),
+        cs.lookups().len(),
+        cs.degree(),
+        cs.blinding_factors() + 1,
+        proof_size,
+    )
+}
+
+/// Draws the 9x9 circuit's layout at `k` to an SVG at `path`: one column per
+/// advice, instance, fixed and selector column, the regions shaded, and the
+/// cells each gate queries marked. Grid row `i` shows up as advice column
+/// `i`, nine rows tall.
+#[cfg(feature = "dev-graph")]
+pub fn render_layout(k: u32, path: &Path) -> Result<(), SudokuError> {
+    use plotters::prelude::*;
+
+    let root = SVGBackend::new(path, (1024, 768)).into_drawing_area();
+    root.fill(&WHITE)
+        .map_err(|e| SudokuError::Render(e.to_string()))?;
+
+    // as in `cost_report`, any witness of the right shape
+    let circuit = MyCircuit::new(vec![vec![Fp::one(); 9]; 9])?;
+    CircuitLayout::default()
+        .render(k, &circuit, &root)
+        .map_err(|e| SudokuError::Render(e.to_string()))?;
+    root.present()
+        .map_err(|e| SudokuError::Render(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cost_report, diagnose, parse_constraint, GridLocation};
+    use crate::{
+        circuit::MyCircuit,
+        fixtures::example_grids,
+        io::{packed_instance, to_field_grid},
+    };
+    use halo2_proofs::pasta::Fp;
+
+    #[test]
+    fn parses_constraint_display() {
+        assert_eq!(
+            parse_constraint("Constraint 3 in gate 2 ('rows')"),
+            Some((3, "rows".to_string()))
+        );
+        assert_eq!(
+            parse_constraint("Constraint 0 ('sum') in gate 4 ('boxes')"),
+            Some((0, "boxes".to_string()))
+        );
+    }
+
+    #[test]
+    fn reports_every_violated_gate() {
+        // (0, 3) is 6 in the real solution; 5 repeats both in row 0 and in
+        // column 3.
+        let (public_grid, mut solution) = example_grids();
+        solution[0][3] = 5;
+
+        let circuit = MyCircuit::<Fp> {
+            solution: to_field_grid(solution),
+            chunked: false,
+        };
+        let instance = packed_instance(&to_field_grid(public_grid));
+        let report = diagnose::<Fp, _>(7, &circuit, instance, 9).unwrap();
+
+        let located = |location| report.iter().any(|f| f.location == Some(location));
+        assert!(report.iter().any(|f| f.gate == "rows"));
+        assert!(report.iter().any(|f| f.gate == "columns"));
+        assert!(located(GridLocation::Row(0)));
+        assert!(located(GridLocation::Column(3)));
+    }
+
+    #[test]
+    fn cost_report_lists_columns() {
+        let report = cost_report(7);
+        assert!(report.contains("advice columns: 18"), "{}", report);
+        assert!(report.contains("lookups: 9"), "{}", report);
+    }
+
+    #[cfg(feature = "dev-graph")]
+    #[test]
+    fn renders_an_svg() {
+        let path = std::env::temp_dir().join("halo2_sudoku_layout.svg");
+        super::render_layout(7, &path).unwrap();
+        let svg = std::fs::read_to_string(&path).unwrap();
+        assert!(svg.starts_with("<svg"), "{}", &svg[..svg.len().min(80)]);
+    }
+}
@@ -0,0 +1,154 @@
+use halo2_proofs::plonk;
+use std::fmt;
+
+use crate::{circuit::Cell, diagnostics::GridLocation};
+
+/// Why one of the crate's helpers could not produce a result.
+#[derive(Debug)]
+pub enum SudokuError {
+    /// A puzzle line held `len` cells rather than `expected`.
+    BadLength { len: usize, expected: usize },
+    /// A puzzle line held something other than a digit or a blank marker.
+    InvalidCharacter { index: usize, character: char },
+    /// A compact grid string had the wrong length or encoded a row that is
+    /// not nine decimal digits.
+    InvalidCompact { reason: &'static str },
+    /// A grid that should be complete has a blank.
+    Incomplete { row: usize, col: usize },
+    /// A cell is both a clue of the puzzle and set in the witness.
+    ClueOverlap { row: usize, col: usize },
+    /// A cell holds something other than a blank or a digit in `1..=max`.
+    ValueOutOfRange {
+        row: usize,
+        col: usize,
+        value: u64,
+        max: u64,
+    },
+    /// `value` appears at both `first` and `second`, which share `unit`.
+    DuplicateInUnit {
+        unit: GridLocation,
+        value: u64,
+        first: Cell,
+        second: Cell,
+    },
+    /// A grid did not match the board size: it had `rows` rows, and `cols` is
+    /// the length of the first row of the wrong length (the board size if
+    /// every row is right).
+    BadDimensions { rows: usize, cols: usize },
+    /// A circuit over several grids was given `count` of them rather than
+    /// `expected`.
+    BadGridCount { count: usize, expected: usize },
+    /// A puzzle has no solution.
+    Unsolvable,
+    /// A puzzle has more than one solution.
+    NotUnique,
+    /// A proof envelope was malformed, or was written for another format,
+    /// curve or circuit.
+    InvalidProof { reason: &'static str },
+    /// Keygen, proving or verification failed.
+    Proof(plonk::Error),
+    /// Reading puzzles from SQLite failed.
+    #[cfg(feature = "sqlite")]
+    Database(rusqlite::Error),
+    /// Drawing or writing a layout image failed.
+    #[cfg(feature = "dev-graph")]
+    Render(String),
+}
+
+impl fmt::Display for SudokuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SudokuError::BadLength { len, expected } => {
+                write!(f, "expected {} cells, found {}", expected, len)
+            }
+            SudokuError::InvalidCharacter { index, character } => {
+                write!(f, "invalid character {:?} at position {}", character, index)
+            }
+            SudokuError::InvalidCompact { reason } => {
+                write!(f, "invalid compact grid string: {}", reason)
+            }
+            SudokuError::Incomplete { row, col } => {
+                write!(f, "grid is incomplete: cell ({},{}) is blank", row, col)
+            }
+            SudokuError::ClueOverlap { row, col } => {
+                write!(
+                    f,
+                    "cell ({},{}) is both a clue and in the witness",
+                    row, col
+                )
+            }
+            SudokuError::ValueOutOfRange {
+                row,
+                col,
+                value,
+                max,
+            } => {
+                write!(
+                    f,
+                    "cell ({},{}) holds {}, out of range 1..={}",
+                    row, col, value, max
+                )
+            }
+            SudokuError::DuplicateInUnit {
+                unit,
+                value,
+                first,
+                second,
+            } => {
+                let unit = match unit {
+                    GridLocation::Row(i) => format!("row {}", i),
+                    GridLocation::Column(i) => format!("column {}", i),
+                    GridLocation::Box(i) => format!("box {}", i),
+                    GridLocation::Cell((row, col)) => format!("cell ({},{})", row, col),
+                };
+                write!(
+                    f,
+                    "duplicate {} in {} at ({},{}) and ({},{})",
+                    value, unit, first.0, first.1, second.0, second.1
+                )
+            }
+            SudokuError::BadDimensions { rows, cols } => {
+                write!(
+                    f,
+                    "grid does not match the board: {} rows, a row of {} cells",
+                    rows, cols
+                )
+            }
+            SudokuError::BadGridCount { count, expected } => {
+                write!(f, "expected {} grids, found {}", expected, count)
+            }
+            SudokuError::Unsolvable => write!(f, "puzzle has no solution"),
+            SudokuError::NotUnique => write!(f, "puzzle has more than one solution"),
+            SudokuError::InvalidProof { reason } => write!(f, "invalid proof: {}", reason),
+            SudokuError::Proof(e) => write!(f, "proof system error: {}", e),
+            #[cfg(feature = "sqlite")]
+            SudokuError::Database(e) => write!(f, "database error: {}", e),
+            #[cfg(feature = "dev-graph")]
+            SudokuError::Render(e) => write!(f, "rendering failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SudokuError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SudokuError::Proof(e) => Some(e),
+            #[cfg(feature = "sqlite")]
+            SudokuError::Database(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<plonk::Error> for SudokuError {
+    fn from(e: plonk::Error) -> Self {
+        SudokuError::Proof(e)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for SudokuError {
+    fn from(e: rusqlite::Error) -> Self {
+        SudokuError::Database(e)
+    }
+}
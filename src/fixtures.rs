@@ -0,0 +1,39 @@
+//! The example puzzle the unit tests share. It is the `example` case of the
+//! set `examples/replay.rs` re-proves, so both read it from
+//! `examples/puzzles.txt`.
+
+use crate::{
+    io::{parse_puzzle, split_grid},
+    solver::to_vec_grid,
+};
+
+const PUZZLES: &str = include_str!("../examples/puzzles.txt");
+
+/// The example's puzzle and solution lines, with `.` for blanks.
+pub(crate) fn example_lines() -> (&'static str, &'static str) {
+    PUZZLES
+        .lines()
+        .find_map(
+            |line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+                ["example", puzzle, solution] => Some((puzzle, solution)),
+                _ => None,
+            },
+        )
+        .expect("puzzles.txt holds the example case")
+}
+
+/// The example's clues, zero for blanks, and its private witness: the
+/// solution's digit in every blank and zero at every clue.
+pub(crate) fn example_arrays() -> ([[u8; 9]; 9], [[u8; 9]; 9]) {
+    let (puzzle, solution) = example_lines();
+    let clues = parse_puzzle(puzzle).unwrap();
+    let given = clues.map(|row| row.map(|cell| cell != 0));
+    split_grid(&parse_puzzle(solution).unwrap(), &given)
+}
+
+/// [`example_arrays`] as the `u64` grids most tests build circuits from:
+/// the public givens and the zero-at-clue private witness.
+pub(crate) fn example_grids() -> (Vec<Vec<u64>>, Vec<Vec<u64>>) {
+    let (clues, witness) = example_arrays();
+    (to_vec_grid(&clues), to_vec_grid(&witness))
+}
@@ -0,0 +1,1079 @@
+//! Conversions between the grids users hold and the field-element layout the
+//! circuit consumes. Nothing here touches halo2's `Circuit` machinery.
+
+use halo2_gadgets::poseidon::primitives::{self as poseidon, ConstantLength, P128Pow5T3};
+use halo2_proofs::{arithmetic::FieldExt, pasta::Fp};
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use crate::{
+    circuit::{puzzle_index, randomness_commitment, COMMITTED_PUZZLES},
+    error::SudokuError,
+    solver::solve_puzzle,
+};
+
+/// Lifts a grid of small integers into field elements, row by row.
+pub fn to_field_grid<F: FieldExt>(grid: Vec<Vec<u64>>) -> Vec<Vec<F>> {
+    grid.into_iter()
+        .map(|row| row.into_iter().map(F::from).collect())
+        .collect()
+}
+
+/// Parses an 81-character puzzle line, read row-major. Digits `1`-`9` are
+/// givens; `0` and `.` are blanks. Surrounding whitespace is ignored.
+pub fn parse_line(line: &str) -> Result<Vec<Vec<u64>>, SudokuError> {
+    parse_line_with_blank(line.trim(), &['.', '0'])
+}
+
+/// Like [`parse_line`], but any character in `blanks` marks a blank, for
+/// datasets that use `_`, `*` or a space. Characters that are neither `1`-`9`
+/// nor in `blanks` are rejected, `0` included unless listed. Only a trailing
+/// line ending is stripped, so a space can be a blank.
+pub fn parse_line_with_blank(line: &str, blanks: &[char]) -> Result<Vec<Vec<u64>>, SudokuError> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let len = line.chars().count();
+    if len != 81 {
+        return Err(SudokuError::BadLength { len, expected: 81 });
+    }
+
+    let cells = line
+        .chars()
+        .enumerate()
+        .map(|(index, character)| match character {
+            c if blanks.contains(&c) => Ok(0),
+            '1'..='9' => Ok(u64::from(character as u8 - b'0')),
+            _ => Err(SudokuError::InvalidCharacter { index, character }),
+        })
+        .collect::<Result<Vec<u64>, _>>()?;
+    Ok(cells.chunks(9).map(<[u64]>::to_vec).collect())
+}
+
+/// Like [`parse_line`], into a fixed 9x9 grid, zero for blanks.
+pub fn parse_puzzle(s: &str) -> Result<[[u8; 9]; 9], SudokuError> {
+    let mut grid = [[0; 9]; 9];
+    for (row, values) in grid.iter_mut().zip(parse_line(s)?) {
+        for (cell, value) in row.iter_mut().zip(values) {
+            *cell = value as u8;
+        }
+    }
+    Ok(grid)
+}
+
+/// Like [`parse_puzzle`], for a grid written as nine lines of nine cells,
+/// such as SadMan `.sdk` files. Whitespace and the box separators `|`, `-`
+/// and `+` are ignored, so are lines holding nothing else and `#` comment
+/// lines; `.` and `0` are blanks. Anything other than nine lines of nine
+/// cells fails with [`SudokuError::BadDimensions`], reporting the first line
+/// of the wrong length.
+pub fn parse_grid_multiline(s: &str) -> Result<[[u8; 9]; 9], SudokuError> {
+    let lines: Vec<String> = s
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .map(|line| {
+            line.chars()
+                .filter(|c| !c.is_whitespace() && !matches!(c, '|' | '-' | '+'))
+                .collect()
+        })
+        .filter(|line: &String| !line.is_empty())
+        .collect();
+
+    let rows = lines.len();
+    let cols = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .find(|&len| len != 9)
+        .unwrap_or(9);
+    if rows != 9 || cols != 9 {
+        return Err(SudokuError::BadDimensions { rows, cols });
+    }
+    parse_puzzle(&lines.concat())
+}
+
+/// A 9x9 grid of digits indexed `[row][col]`, zero for blanks: a puzzle, a
+/// solution or a witness. It parses from and displays as the 81-character
+/// line [`parse_puzzle`] reads, with `.` for blanks, and with the `serde`
+/// feature (de)serializes as nested arrays. The alternate form, `{:#}`,
+/// prints nine rows boxed in by `+---+---+---+` separators, which
+/// [`parse_grid_multiline`] reads back.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Grid(pub [[u8; 9]; 9]);
+
+impl FromStr for Grid {
+    type Err = SudokuError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_puzzle(s).map(Grid)
+    }
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cell = |f: &mut fmt::Formatter<'_>, value: u8| match value {
+            0 => write!(f, "."),
+            _ => write!(f, "{}", value),
+        };
+        if !f.alternate() {
+            for &value in self.0.iter().flatten() {
+                cell(f, value)?;
+            }
+            return Ok(());
+        }
+
+        const SEPARATOR: &str = "+---+---+---+";
+        writeln!(f, "{}", SEPARATOR)?;
+        for (row, values) in self.0.iter().enumerate() {
+            for (col, &value) in values.iter().enumerate() {
+                if col % 3 == 0 {
+                    write!(f, "|")?;
+                }
+                cell(f, value)?;
+            }
+            writeln!(f, "|")?;
+            if row % 3 == 2 {
+                write!(f, "{}", SEPARATOR)?;
+                if row < 8 {
+                    writeln!(f)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<[[u8; 9]; 9]> for Grid {
+    fn from(cells: [[u8; 9]; 9]) -> Self {
+        Grid(cells)
+    }
+}
+
+impl From<Grid> for [[u8; 9]; 9] {
+    fn from(grid: Grid) -> Self {
+        grid.0
+    }
+}
+
+/// The grid as field elements, as [`MyCircuit`](crate::MyCircuit) and
+/// [`packed_instance`] take it.
+impl<F: FieldExt> From<Grid> for Vec<Vec<F>> {
+    fn from(grid: Grid) -> Self {
+        grid.0
+            .iter()
+            .map(|row| row.iter().map(|&value| F::from(u64::from(value))).collect())
+            .collect()
+    }
+}
+
+/// Reads a grid of field elements back, failing with
+/// [`SudokuError::BadDimensions`] unless it is 9x9 and with
+/// [`SudokuError::ValueOutOfRange`] at the first cell that is not zero or a
+/// digit.
+impl<F: FieldExt> TryFrom<&[Vec<F>]> for Grid {
+    type Error = SudokuError;
+
+    fn try_from(cells: &[Vec<F>]) -> Result<Self, Self::Error> {
+        let rows = cells.len();
+        let cols = cells
+            .iter()
+            .map(Vec::len)
+            .find(|&len| len != 9)
+            .unwrap_or(9);
+        if rows != 9 || cols != 9 {
+            return Err(SudokuError::BadDimensions { rows, cols });
+        }
+
+        let mut grid = [[0; 9]; 9];
+        for (row, values) in cells.iter().enumerate() {
+            for (col, &value) in values.iter().enumerate() {
+                let lower = value.get_lower_128();
+                if lower > 9 || F::from(lower as u64) != value {
+                    return Err(SudokuError::ValueOutOfRange {
+                        row,
+                        col,
+                        value: lower as u64,
+                        max: 9,
+                    });
+                }
+                grid[row][col] = lower as u8;
+            }
+        }
+        Ok(Grid(grid))
+    }
+}
+
+/// The private witness `MyCircuit` expects for the puzzle `clues`: the
+/// completed grid `full` with every clue position zeroed, so each given is
+/// only ever read from the instance.
+pub fn solution_witness<F: FieldExt>(clues: &[[u8; 9]; 9], full: &[[u8; 9]; 9]) -> Vec<Vec<F>> {
+    clues
+        .iter()
+        .zip(full)
+        .map(|(givens, cells)| {
+            givens
+                .iter()
+                .zip(cells)
+                .map(|(&given, &cell)| {
+                    if given == 0 {
+                        F::from(u64::from(cell))
+                    } else {
+                        F::zero()
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Solves every puzzle in `puzzles` and builds its [`solution_witness`], for
+/// a batch circuit such as `BatchSudokuCircuit`. With the `parallel` feature
+/// the puzzles are solved concurrently on rayon's thread pool, except on
+/// `wasm32`; either way the result is the same, in the order given. Fails
+/// with [`SudokuError::Unsolvable`] if any puzzle has no solution.
+pub fn batch_witnesses<F: FieldExt>(
+    puzzles: &[[[u8; 9]; 9]],
+) -> Result<Vec<Vec<Vec<F>>>, SudokuError> {
+    let witness = |clues: &[[u8; 9]; 9]| {
+        let full = solve_puzzle(clues).ok_or(SudokuError::Unsolvable)?;
+        Ok(solution_witness(clues, &full))
+    };
+
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    let puzzles = {
+        use rayon::prelude::*;
+        puzzles.par_iter()
+    };
+    #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+    let puzzles = puzzles.iter();
+
+    puzzles.map(witness).collect()
+}
+
+/// The complete grid: `witness` overlaid on `clues`, where a zero in
+/// `witness` takes the clue at that position.
+pub fn merge_grid(clues: &[[u8; 9]; 9], witness: &[[u8; 9]; 9]) -> [[u8; 9]; 9] {
+    let mut grid = *clues;
+    for (row, cells) in grid.iter_mut().zip(witness) {
+        for (cell, &value) in row.iter_mut().zip(cells) {
+            if value != 0 {
+                *cell = value;
+            }
+        }
+    }
+    grid
+}
+
+/// The inverse of [`merge_grid`]: splits `grid` into the clues at the
+/// positions `given` marks and the witness everywhere else, each zero where
+/// the other holds the cell.
+pub fn split_grid(grid: &[[u8; 9]; 9], given: &[[bool; 9]; 9]) -> ([[u8; 9]; 9], [[u8; 9]; 9]) {
+    // `grid` with every cell zeroed whose mark is not `keep`
+    let pick = |keep: bool| {
+        let mut out = *grid;
+        for (row, marks) in out.iter_mut().zip(given) {
+            for (cell, &mark) in row.iter_mut().zip(marks) {
+                if mark != keep {
+                    *cell = 0;
+                }
+            }
+        }
+        out
+    };
+    (pick(true), pick(false))
+}
+
+/// The circuit's public input for `puzzle`, indexed `[row][col]`: a single
+/// instance column holding the cells row-major, so cell `(row, col)` of a
+/// 9x9 grid is at `row * 9 + col`.
+pub fn packed_instance<F: Clone>(puzzle: &[Vec<F>]) -> Vec<Vec<F>> {
+    vec![puzzle.concat()]
+}
+
+/// Checks that every cell of the packed instance column `public`, for an
+/// `n x n` board, is zero (blank) or a digit in `1..=n`, failing with
+/// [`SudokuError::ValueOutOfRange`] at the first that is not. The circuit
+/// would only reject such clues at proving time, if at all.
+pub fn check_instance<F: FieldExt>(public: &[F], n: usize) -> Result<(), SudokuError> {
+    for (i, &value) in public.iter().enumerate() {
+        let lower = value.get_lower_128();
+        if lower > n as u128 || F::from(lower as u64) != value {
+            return Err(SudokuError::ValueOutOfRange {
+                row: i / n,
+                col: i % n,
+                value: lower as u64,
+                max: n as u64,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Builds the instance column from a sparse map of `(row, col)` givens,
+/// leaving every other position as the zero blank. A given off the board
+/// fails with [`SudokuError::BadDimensions`], reporting the smallest grid
+/// that would hold it, and a value above 9 with
+/// [`SudokuError::ValueOutOfRange`].
+pub fn instance_from_givens<F: FieldExt>(
+    givens: &HashMap<(usize, usize), u64>,
+) -> Result<Vec<Vec<F>>, SudokuError> {
+    let mut cells = vec![F::zero(); 81];
+    for (&(row, col), &value) in givens {
+        if row >= 9 || col >= 9 {
+            return Err(SudokuError::BadDimensions {
+                rows: row.max(8) + 1,
+                cols: col.max(8) + 1,
+            });
+        }
+        if value > 9 {
+            return Err(SudokuError::ValueOutOfRange {
+                row,
+                col,
+                value,
+                max: 9,
+            });
+        }
+        cells[row * 9 + col] = F::from(value);
+    }
+    Ok(vec![cells])
+}
+
+const COMPACT_ALPHABET: &[u8; 36] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const COMPACT_ROW_LEN: usize = 6;
+
+/// Encodes a grid (digits `0`-`9`, zero for blanks) as 54 base36 characters:
+/// each row, read as a nine-digit decimal number, becomes six characters.
+/// The output is URL-safe and uppercase, so QR encoders can use their compact
+/// alphanumeric mode.
+///
+/// A grid that is not 9x9 fails with [`SudokuError::BadDimensions`] and a
+/// cell above 9 with [`SudokuError::ValueOutOfRange`], so every row fits its
+/// six characters and the encoding is never lossy.
+pub fn to_compact_string(grid: &[Vec<u64>]) -> Result<String, SudokuError> {
+    let rows = grid.len();
+    let cols = grid.iter().map(Vec::len).find(|&len| len != 9).unwrap_or(9);
+    if rows != 9 || cols != 9 {
+        return Err(SudokuError::BadDimensions { rows, cols });
+    }
+
+    let mut out = String::with_capacity(9 * COMPACT_ROW_LEN);
+    for (row, cells) in grid.iter().enumerate() {
+        if let Some(col) = cells.iter().position(|&value| value > 9) {
+            return Err(SudokuError::ValueOutOfRange {
+                row,
+                col,
+                value: cells[col],
+                max: 9,
+            });
+        }
+        let mut value = cells.iter().fold(0, |acc, &digit| acc * 10 + digit);
+        let mut chunk = [b'0'; COMPACT_ROW_LEN];
+        for c in chunk.iter_mut().rev() {
+            *c = COMPACT_ALPHABET[(value % 36) as usize];
+            value /= 36;
+        }
+        out.extend(chunk.iter().map(|&c| c as char));
+    }
+    Ok(out)
+}
+
+/// Decodes the output of [`to_compact_string`]. Lowercase letters are
+/// accepted too.
+pub fn from_compact_string(s: &str) -> Result<Vec<Vec<u64>>, SudokuError> {
+    let len = s.chars().count();
+    if len != 9 * COMPACT_ROW_LEN {
+        return Err(SudokuError::InvalidCompact {
+            reason: "expected 54 characters",
+        });
+    }
+
+    let chars: Vec<char> = s.chars().collect();
+    chars
+        .chunks(COMPACT_ROW_LEN)
+        .enumerate()
+        .map(|(chunk_index, chunk)| {
+            let mut value = 0u64;
+            for (offset, &character) in chunk.iter().enumerate() {
+                let digit = character
+                    .to_digit(36)
+                    .ok_or(SudokuError::InvalidCharacter {
+                        index: chunk_index * COMPACT_ROW_LEN + offset,
+                        character,
+                    })?;
+                value = value * 36 + u64::from(digit);
+            }
+            if value >= 1_000_000_000 {
+                return Err(SudokuError::InvalidCompact {
+                    reason: "row value exceeds nine digits",
+                });
+            }
+
+            let mut row = vec![0; 9];
+            for cell in row.iter_mut().rev() {
+                *cell = value % 10;
+                value /= 10;
+            }
+            Ok(row)
+        })
+        .collect()
+}
+
+/// The values `SudokuChip::assign` places in the nine grid advice columns,
+/// indexed `[column][row]`: column `i` holds grid row `i`, so this is the
+/// puzzle with its blanks filled from `solution`. The `givens` columns are a
+/// verbatim copy of the instance and are not repeated here.
+pub fn expected_advice<F: FieldExt>(puzzle: &[Vec<F>], solution: &[Vec<F>]) -> Vec<Vec<F>> {
+    puzzle
+        .iter()
+        .zip(solution)
+        .map(|(givens, witness)| {
+            givens
+                .iter()
+                .zip(witness)
+                .map(|(&given, &value)| if value == F::zero() { given } else { value })
+                .collect()
+        })
+        .collect()
+}
+
+/// How many public field elements a verifier of the plain `N x N` sudoku
+/// circuit supplies: all `N * N` puzzle cells, blanks included, in its one
+/// instance column.
+pub fn num_instances<const N: usize>() -> usize {
+    N * N
+}
+
+/// Like [`num_instances`], for the 9x9 `CommittedCircuit`: the puzzle plus
+/// the one-cell commitment column of [`committed_instance`].
+pub fn num_committed_instances() -> usize {
+    num_instances::<9>() + 1
+}
+
+/// Like [`num_instances`], for the 9x9 [`HashedCircuit`](crate::HashedCircuit):
+/// the puzzle plus the one-cell hash column of [`hashed_instance`]. The hash
+/// is published next to the grid rather than in place of it, so no layout
+/// publishes fewer than 81 elements.
+pub fn num_hashed_instances() -> usize {
+    num_instances::<9>() + 1
+}
+
+/// Builds the public input for [`CommittedCircuit`](crate::CommittedCircuit)
+/// from its table `puzzles` and the server's private `randomness`: the
+/// packed column of the puzzle `randomness` selects (see [`puzzle_index`])
+/// followed by the [`randomness_commitment`] the server publishes before
+/// assigning it. The circuit derives the same selection from the committed
+/// randomness, so a proof only verifies for that puzzle.
+pub fn committed_instance(
+    puzzles: &[[[u8; 9]; 9]; COMMITTED_PUZZLES],
+    randomness: Fp,
+) -> Vec<Vec<Fp>> {
+    let puzzle = Vec::<Vec<Fp>>::from(Grid(puzzles[puzzle_index(randomness)]));
+    let mut public = packed_instance(&puzzle);
+    public.push(vec![randomness_commitment(randomness)]);
+    public
+}
+
+/// The hash a [`HashedCircuit`](crate::HashedCircuit) exposes for the
+/// completed grid `full` under the private `salt`: Poseidon over its 81
+/// cells, row-major, followed by the salt.
+pub fn solution_hash(full: &[[u8; 9]; 9], salt: Fp) -> Fp {
+    let cells: Vec<Fp> = full
+        .iter()
+        .flatten()
+        .map(|&value| Fp::from(u64::from(value)))
+        .chain([salt])
+        .collect();
+    poseidon::Hash::<_, P128Pow5T3, ConstantLength<82>, 3, 2>::init()
+        .hash(cells.try_into().expect("81 cells and the salt"))
+}
+
+/// Appends the instance column of a [`HashedCircuit`](crate::HashedCircuit),
+/// holding `hash`, to the puzzle's `public` input. `hash` is the
+/// [`solution_hash`] the prover publishes; a verifier never learns the salt
+/// behind it.
+pub fn hashed_instance(mut public: Vec<Vec<Fp>>, hash: Fp) -> Vec<Vec<Fp>> {
+    public.push(vec![hash]);
+    public
+}
+
+/// Renders a self-contained `#[test]` that reproduces a MockProver run on the
+/// given grids, in the same shape as `sudoku_example`. It imports what it
+/// uses from `halo2_sudoku` and `halo2_proofs`, so the output can be pasted
+/// into a file under `tests/` to attach a failing grid to a bug report.
+///
+/// `puzzle` holds the public givens (zero for blanks) and `solution` the
+/// private witness (zero at the given positions).
+pub fn repro_case(puzzle: &[Vec<u64>], solution: &[Vec<u64>]) -> String {
+    fn rows(grid: &[Vec<u64>]) -> String {
+        grid.iter()
+            .map(|row| {
+                let cells: Vec<String> = row.iter().map(|v| v.to_string()).collect();
+                format!("        vec![{}],\n", cells.join(", "))
+            })
+            .collect()
+    }
+
+    format!(
+        "\
+#[test]
+fn sudoku_repro() {{
+    use halo2_proofs::{{dev::MockProver, pasta::Fp}};
+    use halo2_sudoku::MyCircuit;
+
+    let k = 7;
+
+    let public_grid = vec![
+{}    ];
+
+    let solution = vec![
+{}    ];
+
+    let to_fp = |grid: Vec<Vec<u64>>| -> Vec<Vec<Fp>> {{
+        grid.into_iter()
+            .map(|row| row.into_iter().map(Fp::from).collect())
+            .collect()
+    }};
+
+    let circuit = MyCircuit::<Fp>::new(to_fp(solution)).unwrap();
+
+    let instance = vec![to_fp(public_grid).concat()];
+    let prover = MockProver::run(k, &circuit, instance).unwrap();
+    prover.assert_satisfied();
+}}
+",
+        rows(puzzle),
+        rows(solution)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        batch_witnesses, check_instance, committed_instance, expected_advice, from_compact_string,
+        hashed_instance, instance_from_givens, merge_grid, num_committed_instances,
+        num_hashed_instances, num_instances, packed_instance, parse_grid_multiline, parse_line,
+        parse_line_with_blank, parse_puzzle, repro_case, solution_witness, split_grid,
+        to_compact_string, to_field_grid, Grid,
+    };
+    use crate::circuit::{
+        puzzle_index, randomness_commitment, MyCircuit, SudokuChip, SudokuConfig, COMMITTED_PUZZLES,
+    };
+    use crate::error::SudokuError;
+    use crate::fixtures::{example_arrays, example_grids, example_lines};
+    use crate::solver::{solve_puzzle, to_vec_grid};
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+    };
+    use std::collections::HashMap;
+
+    #[test]
+    fn parse_line_reads_rows_and_blanks() {
+        let line = format!("5.{}", "0".repeat(78) + "9");
+        let grid = parse_line(&line).unwrap();
+        assert_eq!(grid.len(), 9);
+        assert_eq!(grid[0][..2], [5, 0]);
+        assert_eq!(grid[8][8], 9);
+
+        assert!(matches!(
+            parse_line("123"),
+            Err(SudokuError::BadLength {
+                len: 3,
+                expected: 81
+            })
+        ));
+        assert!(matches!(
+            parse_line(&format!("x{}", "0".repeat(80))),
+            Err(SudokuError::InvalidCharacter {
+                index: 0,
+                character: 'x'
+            })
+        ));
+    }
+
+    #[test]
+    fn parse_line_with_custom_blanks() {
+        let dots = format!("5.{}9", ".".repeat(78));
+        let expected = parse_line(&dots).unwrap();
+
+        for blank in ['_', '*', ' '] {
+            let line = dots.replace('.', &blank.to_string());
+            assert_eq!(parse_line_with_blank(&line, &[blank]).unwrap(), expected);
+        }
+        assert_eq!(
+            parse_line_with_blank(&format!("{}\n", dots.replace('.', "_")), &['_', '*']).unwrap(),
+            expected
+        );
+
+        // '.' and '0' are only blanks when listed
+        assert!(matches!(
+            parse_line_with_blank(&dots, &['_']),
+            Err(SudokuError::InvalidCharacter {
+                index: 1,
+                character: '.'
+            })
+        ));
+        assert!(matches!(
+            parse_line_with_blank(&dots.replace('.', "0"), &['*']),
+            Err(SudokuError::InvalidCharacter {
+                index: 1,
+                character: '0'
+            })
+        ));
+    }
+
+    #[test]
+    fn to_field_grid_preserves_layout() {
+        let grid: Vec<Vec<Fp>> = to_field_grid(vec![vec![1, 2], vec![3, 0]]);
+
+        assert_eq!(
+            grid,
+            vec![
+                vec![Fp::from(1), Fp::from(2)],
+                vec![Fp::from(3), Fp::zero()]
+            ]
+        );
+    }
+
+    #[test]
+    fn instance_from_givens_zero_fills() {
+        let givens = HashMap::from([((0, 2), 1), ((4, 3), 8), ((8, 6), 4)]);
+
+        let mut full = vec![vec![0; 9]; 9];
+        full[0][2] = 1;
+        full[4][3] = 8;
+        full[8][6] = 4;
+
+        assert_eq!(
+            instance_from_givens::<Fp>(&givens).unwrap(),
+            packed_instance(&to_field_grid::<Fp>(full))
+        );
+
+        assert!(matches!(
+            instance_from_givens::<Fp>(&HashMap::from([((0, 9), 1)])),
+            Err(SudokuError::BadDimensions { rows: 9, cols: 10 })
+        ));
+        assert!(matches!(
+            instance_from_givens::<Fp>(&HashMap::from([((9, 0), 1)])),
+            Err(SudokuError::BadDimensions { rows: 10, cols: 9 })
+        ));
+        assert!(matches!(
+            instance_from_givens::<Fp>(&HashMap::from([((4, 3), 10)])),
+            Err(SudokuError::ValueOutOfRange {
+                row: 4,
+                col: 3,
+                value: 10,
+                max: 9
+            })
+        ));
+    }
+
+    #[test]
+    fn errors_report_the_board_they_expected() {
+        let mut public = vec![Fp::zero(); 16];
+        assert!(check_instance(&public, 4).is_ok());
+        public[6] = Fp::from(5);
+        let err = check_instance(&public, 4).unwrap_err();
+        assert!(matches!(
+            err,
+            SudokuError::ValueOutOfRange {
+                row: 1,
+                col: 2,
+                value: 5,
+                max: 4
+            }
+        ));
+        assert_eq!(err.to_string(), "cell (1,2) holds 5, out of range 1..=4");
+
+        assert_eq!(
+            parse_line("123").unwrap_err().to_string(),
+            "expected 81 cells, found 3"
+        );
+    }
+
+    #[test]
+    fn committed_instance_appends_commitment_column() {
+        // a table whose puzzles each hold their index in the first cell
+        let mut puzzles = [[[0; 9]; 9]; COMMITTED_PUZZLES];
+        for (index, puzzle) in puzzles.iter_mut().enumerate() {
+            puzzle[0][0] = index as u8 + 1;
+        }
+
+        for randomness in (0..8).map(Fp::from) {
+            let public = committed_instance(&puzzles, randomness);
+            assert_eq!(public.len(), 2);
+            assert_eq!(public[0][0], Fp::from(puzzle_index(randomness) as u64 + 1));
+            assert_eq!(public[1], vec![randomness_commitment(randomness)]);
+        }
+        assert_ne!(
+            randomness_commitment(Fp::from(1)),
+            randomness_commitment(Fp::from(2))
+        );
+    }
+
+    #[test]
+    fn instance_counts_match_layouts() {
+        let (public_grid, _) = example_grids();
+        let public: Vec<Vec<Fp>> = packed_instance(&to_field_grid(public_grid));
+        let count = |instance: &[Vec<Fp>]| instance.iter().map(Vec::len).sum::<usize>();
+
+        assert_eq!(num_instances::<9>(), 81);
+        assert_eq!(num_instances::<4>(), 16);
+        assert_eq!(count(&public), num_instances::<9>());
+        assert_eq!(
+            count(&committed_instance(
+                &[[[0; 9]; 9]; COMMITTED_PUZZLES],
+                Fp::from(3)
+            )),
+            num_committed_instances()
+        );
+        assert_eq!(
+            count(&hashed_instance(public, Fp::from(3))),
+            num_hashed_instances()
+        );
+    }
+
+    #[test]
+    fn repro_case_embeds_grids() {
+        let public_grid = vec![vec![0, 0, 1], vec![4, 0, 0]];
+        let solution = vec![vec![5, 7, 0], vec![0, 2, 6]];
+
+        let snippet = repro_case(&public_grid, &solution);
+
+        assert!(snippet.starts_with("#[test]\nfn sudoku_repro() {\n"));
+        assert!(snippet.contains("use halo2_proofs::{dev::MockProver, pasta::Fp};"));
+        assert!(snippet.contains("use halo2_sudoku::MyCircuit;"));
+        assert!(snippet.contains("let public_grid = vec![\n        vec![0, 0, 1],\n"));
+        assert!(snippet.contains("vec![4, 0, 0],"));
+        assert!(snippet.contains("let solution = vec!["));
+        assert!(snippet.contains("vec![5, 7, 0],"));
+        assert!(snippet.contains("vec![0, 2, 6],"));
+        // the public constructor, not the circuit's crate-private fields
+        assert!(snippet.contains("MyCircuit::<Fp>::new(to_fp(solution)).unwrap()"));
+        assert!(!snippet.contains("chunked"));
+        assert!(snippet.contains("MockProver::run(k, &circuit, instance)"));
+        assert!(snippet.contains("assert_satisfied()"));
+    }
+
+    #[test]
+    fn compact_string_round_trips() {
+        let (public_grid, solution) = example_grids();
+        let (clues, witness) = example_arrays();
+        let full = to_vec_grid(&merge_grid(&clues, &witness));
+
+        for grid in [full, public_grid, solution] {
+            let compact = to_compact_string(&grid).unwrap();
+            assert_eq!(compact.len(), 54);
+            assert!(compact.bytes().all(|b| b.is_ascii_alphanumeric()));
+            assert_eq!(from_compact_string(&compact).unwrap(), grid);
+            assert_eq!(from_compact_string(&compact.to_lowercase()).unwrap(), grid);
+        }
+
+        assert!(from_compact_string("ABC").is_err());
+        assert!(from_compact_string(&"Z".repeat(54)).is_err());
+        assert!(from_compact_string(&"-".repeat(54)).is_err());
+    }
+
+    #[test]
+    fn compact_string_rejects_bad_grids() {
+        let (public_grid, _) = example_grids();
+
+        let mut too_big = public_grid.clone();
+        too_big[2][3] = 10;
+        assert!(matches!(
+            to_compact_string(&too_big),
+            Err(SudokuError::ValueOutOfRange {
+                row: 2,
+                col: 3,
+                value: 10,
+                max: 9
+            })
+        ));
+
+        let mut long_row = public_grid.clone();
+        long_row[4].push(0);
+        assert!(matches!(
+            to_compact_string(&long_row),
+            Err(SudokuError::BadDimensions { rows: 9, cols: 10 })
+        ));
+        assert!(matches!(
+            to_compact_string(&public_grid[..8]),
+            Err(SudokuError::BadDimensions { rows: 8, cols: 9 })
+        ));
+    }
+
+    #[test]
+    fn batch_witnesses_match_one_by_one() {
+        let (example, _) = example_arrays();
+
+        // the example with its digits relabelled, each also transposed
+        let mut puzzles = Vec::new();
+        for shift in 0..8 {
+            let relabelled =
+                example.map(|row| row.map(|v| if v == 0 { 0 } else { (v + shift - 1) % 9 + 1 }));
+            let mut transposed = [[0; 9]; 9];
+            for (r, row) in relabelled.iter().enumerate() {
+                for (c, &v) in row.iter().enumerate() {
+                    transposed[c][r] = v;
+                }
+            }
+            puzzles.push(relabelled);
+            puzzles.push(transposed);
+        }
+
+        let one_by_one: Vec<Vec<Vec<Fp>>> = puzzles
+            .iter()
+            .map(|clues| solution_witness(clues, &solve_puzzle(clues).unwrap()))
+            .collect();
+        assert_eq!(batch_witnesses::<Fp>(&puzzles).unwrap(), one_by_one);
+
+        let mut unsolvable = example;
+        unsolvable[0][0] = 1; // a second 1 in row 0
+        puzzles.push(unsolvable);
+        assert!(matches!(
+            batch_witnesses::<Fp>(&puzzles),
+            Err(SudokuError::Unsolvable)
+        ));
+    }
+
+    #[test]
+    fn merge_undoes_split() {
+        let (puzzle, solution) = example_lines();
+        let clues = parse_puzzle(puzzle).unwrap();
+        let grid = parse_puzzle(solution).unwrap();
+        let given = clues.map(|row| row.map(|cell| cell != 0));
+
+        let (split_clues, witness) = split_grid(&grid, &given);
+        assert_eq!(split_clues, clues);
+        assert_eq!(merge_grid(&split_clues, &witness), grid);
+
+        let witness: Vec<Vec<Fp>> = witness
+            .iter()
+            .map(|row| row.iter().map(|&v| Fp::from(u64::from(v))).collect())
+            .collect();
+        assert_eq!(witness, solution_witness(&clues, &grid));
+
+        // nothing given, and everything given
+        for given in [[[false; 9]; 9], [[true; 9]; 9]] {
+            let (clues, witness) = split_grid(&grid, &given);
+            assert_eq!(merge_grid(&clues, &witness), grid);
+        }
+    }
+
+    #[test]
+    fn parsed_puzzle_builds_the_witness() {
+        let (public_grid, solution) = example_grids();
+        let (line, _) = example_lines();
+
+        let clues = parse_puzzle(line).unwrap();
+        for (parsed, expected) in clues.iter().zip(&public_grid) {
+            assert!(parsed
+                .iter()
+                .zip(expected)
+                .all(|(&a, &b)| u64::from(a) == b));
+        }
+        assert_eq!(parse_puzzle(&line.replace('.', "0")).unwrap(), clues);
+        assert!(parse_puzzle(&line[1..]).is_err());
+
+        let (_, witness) = example_arrays();
+        let full = merge_grid(&clues, &witness);
+
+        let witness: Vec<Vec<Fp>> = solution_witness(&clues, &full);
+        assert_eq!(witness, to_field_grid::<Fp>(solution));
+
+        let circuit = MyCircuit::new(witness).unwrap();
+        let instance = packed_instance(&to_field_grid(public_grid));
+        let prover = MockProver::run(7, &circuit, instance).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn converted_grids_drive_the_circuit() {
+        let (public_grid, solution) = example_grids();
+
+        let circuit = MyCircuit::<Fp> {
+            solution: to_field_grid(solution),
+            chunked: false,
+        };
+        let instance = packed_instance(&to_field_grid(public_grid));
+        let prover = MockProver::run(7, &circuit, instance).unwrap();
+        prover.assert_satisfied();
+    }
+
+    /// The plain sudoku circuit plus nine instance columns that every assigned
+    /// grid cell is constrained to, so MockProver checks each advice value.
+    struct ExpectedCircuit {
+        solution: Vec<Vec<Fp>>,
+    }
+
+    impl Circuit<Fp> for ExpectedCircuit {
+        type Config = (SudokuConfig, [Column<Instance>; 9]);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                solution: Vec::new(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let sudoku = SudokuChip::configure(meta);
+            let expected = [0; 9].map(|_| meta.instance_column());
+            for column in expected {
+                meta.enable_equality(column);
+            }
+            (sudoku, expected)
+        }
+
+        fn synthesize(
+            &self,
+            (sudoku, expected): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let grid = SudokuChip::construct(sudoku)
+                .assign(layouter.namespace(|| "sudoku"), &self.solution)?;
+            for (column, cells) in expected.into_iter().zip(grid) {
+                for (row, cell) in cells.iter().enumerate() {
+                    layouter.constrain_instance(cell.cell(), column, row)?;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn expected_advice_matches_assigned_cells() {
+        let (public_grid, solution) = example_grids();
+        let public_input: Vec<Vec<Fp>> = to_field_grid(public_grid);
+        let solution: Vec<Vec<Fp>> = to_field_grid(solution);
+
+        let expected = expected_advice(&public_input, &solution);
+        assert_eq!(expected[0][..3], [Fp::from(5), Fp::from(7), Fp::from(1)]);
+
+        let circuit = ExpectedCircuit { solution };
+        let run = |expected: &[Vec<Fp>]| {
+            let instance = packed_instance(&public_input)
+                .into_iter()
+                .chain(expected.iter().cloned())
+                .collect();
+            MockProver::run(7, &circuit, instance).unwrap().verify()
+        };
+
+        assert!(run(&expected).is_ok());
+
+        let mut wrong = expected.clone();
+        wrong[2][0] += Fp::one();
+        assert!(run(&wrong).is_err());
+    }
+
+    #[test]
+    fn grid_round_trips() {
+        let line = ".".repeat(8) + "9" + &"0".repeat(72);
+        let grid: Grid = line.parse().unwrap();
+        assert_eq!(grid.0[0][8], 9);
+        assert_eq!(grid.to_string(), ".".repeat(8) + "9" + &".".repeat(72));
+        assert_eq!(grid.to_string().parse::<Grid>().unwrap(), grid);
+        assert!(matches!(
+            "123".parse::<Grid>(),
+            Err(SudokuError::BadLength {
+                len: 3,
+                expected: 81
+            })
+        ));
+
+        let cells: Vec<Vec<Fp>> = grid.into();
+        assert_eq!(cells[0][8], Fp::from(9));
+        assert_eq!(Grid::try_from(&cells[..]).unwrap(), grid);
+
+        // an element outside 0..=9, whether past the digits or wrapped
+        // around the field
+        let mut out_of_range = cells.clone();
+        out_of_range[2][3] = Fp::from(10);
+        assert!(matches!(
+            Grid::try_from(&out_of_range[..]),
+            Err(SudokuError::ValueOutOfRange {
+                row: 2,
+                col: 3,
+                value: 10,
+                max: 9
+            })
+        ));
+        out_of_range[2][3] = -Fp::one();
+        assert!(matches!(
+            Grid::try_from(&out_of_range[..]),
+            Err(SudokuError::ValueOutOfRange { row: 2, col: 3, .. })
+        ));
+        assert!(matches!(
+            Grid::try_from(&cells[..8]),
+            Err(SudokuError::BadDimensions { rows: 8, cols: 9 })
+        ));
+    }
+
+    #[test]
+    fn alternate_display_draws_boxes() {
+        let line =
+            "001004090400000107080700004900010800000807000008060001800005010605000009010900400";
+        let grid: Grid = line.parse().unwrap();
+        let pretty = format!("{:#}", grid);
+        let lines: Vec<&str> = pretty.lines().collect();
+        assert_eq!(lines.len(), 13);
+        assert_eq!(lines[0], "+---+---+---+");
+        assert_eq!(lines[1], "|..1|..4|.9.|");
+        assert_eq!(lines[4], "+---+---+---+");
+        assert_eq!(lines.iter().filter(|l| l.starts_with('+')).count(), 4);
+        assert_eq!(pretty.matches('|').count(), 9 * 4);
+        assert!(lines.iter().all(|l| l.len() == 13));
+
+        // the plain form is unchanged, and the boxed one reads back
+        assert_eq!(grid.to_string().len(), 81);
+        assert_eq!(Grid(parse_grid_multiline(&pretty).unwrap()), grid);
+    }
+
+    #[test]
+    fn multiline_grids() {
+        let decorated = "\
+# from a .sdk file
+..1|..4|.9.
+4..|...|1.7
+.8.|7..|..4
+---+---+---
+9..|.1.|8..
+...|8.7|...
+..8|.6.|..1
+---+---+---
+8..|..5|.1.
+6.5|...|..9
+.1.|9..|4..
+";
+        let line =
+            "001004090400000107080700004900010800000807000008060001800005010605000009010900400";
+        assert_eq!(
+            Grid(parse_grid_multiline(decorated).unwrap()),
+            line.parse::<Grid>().unwrap()
+        );
+
+        let eight_rows: String = decorated.lines().take(11).collect::<Vec<_>>().join("\n");
+        assert!(matches!(
+            parse_grid_multiline(&eight_rows),
+            Err(SudokuError::BadDimensions { rows: 8, cols: 9 })
+        ));
+        assert!(matches!(
+            parse_grid_multiline(&decorated.replacen("..1|", "..|", 1)),
+            Err(SudokuError::BadDimensions { rows: 9, cols: 8 })
+        ));
+        assert!(matches!(
+            parse_grid_multiline(&decorated.replacen("..1|", "..x|", 1)),
+            Err(SudokuError::InvalidCharacter { character: 'x', .. })
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn grid_json_round_trip() {
+        let (clues, _) = example_arrays();
+        let puzzle = Grid(clues);
+
+        let json = serde_json::to_string(&puzzle).unwrap();
+        assert!(json.starts_with("[[0,0,1,"), "{}", json);
+        assert_eq!(serde_json::from_str::<Grid>(&json).unwrap(), puzzle);
+    }
+}
@@ -0,0 +1,148 @@
+//! `sudoku`, a command line prover and verifier for the 9x9 circuit:
+//!
+//! ```text
+//! sudoku prove --puzzle puzzle.txt --solution solution.txt --out proof.bin
+//! sudoku verify --puzzle puzzle.txt --proof proof.bin
+//! ```
+//!
+//! Grid files hold either one line of 81 cells, as read by `parse_puzzle`,
+//! or nine lines of nine, optionally decorated with box separators, as read
+//! by `parse_grid_multiline`. Digits are clues and `0` or `.` blanks; the
+//! solution is the completed grid, clues included.
+//!
+//! Exits with 0 on success, 1 if proving or verification fails and 2 on a
+//! usage error.
+
+use halo2_proofs::pasta::Fp;
+use halo2_sudoku::{
+    is_satisfied, min_k, packed_instance, parse_grid_multiline, parse_puzzle, prove,
+    solution_witness, validate_solution, Grid, MyCircuit, SudokuVerifier,
+};
+use std::{env, fs, process};
+
+const USAGE: &str = "\
+usage: sudoku prove --puzzle <puzzle.txt> --solution <solution.txt> --out <proof.bin>
+       sudoku verify --puzzle <puzzle.txt> --proof <proof.bin>";
+
+/// The values of the `--name value` pairs in `args`, in the order of
+/// `names`. Every name must be given exactly once, and nothing else.
+fn flags(args: &[String], names: &[&str]) -> Result<Vec<String>, String> {
+    let mut values = vec![None; names.len()];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let i = names
+            .iter()
+            .position(|name| arg.strip_prefix("--") == Some(*name))
+            .ok_or_else(|| format!("unexpected argument {:?}", arg))?;
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} needs a value", arg))?;
+        if values[i].replace(value.clone()).is_some() {
+            return Err(format!("{} given twice", arg));
+        }
+    }
+    names
+        .iter()
+        .zip(values)
+        .map(|(name, value)| value.ok_or_else(|| format!("missing --{}", name)))
+        .collect()
+}
+
+/// Reads a grid file in either of the formats the module docs describe.
+fn read_grid(path: &str) -> Result<[[u8; 9]; 9], String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    let text = text.trim();
+    let grid = if text.lines().count() == 1 {
+        parse_puzzle(text)
+    } else {
+        parse_grid_multiline(text)
+    };
+    grid.map_err(|e| format!("bad grid in {}: {}", path, e))
+}
+
+/// The `k` both subcommands use. Every 9x9 puzzle has the same layout of
+/// rows, so any witness gives the same answer.
+fn circuit_k() -> u32 {
+    let blank = vec![vec![Fp::zero(); 9]; 9];
+    min_k(&MyCircuit::new(blank).unwrap())
+}
+
+fn prove_command(puzzle_path: &str, solution_path: &str, out_path: &str) -> Result<(), String> {
+    let clues = read_grid(puzzle_path)?;
+    let full = read_grid(solution_path)?;
+    validate_solution(&full).map_err(|e| format!("bad solution: {}", e))?;
+
+    let circuit = MyCircuit::<Fp>::new(solution_witness(&clues, &full))
+        .map_err(|e| format!("cannot build circuit: {}", e))?;
+    let public = packed_instance(&Vec::<Vec<Fp>>::from(Grid(clues)));
+    let k = circuit_k();
+    if !is_satisfied(k, &circuit, &public) {
+        return Err("the solution does not complete the puzzle".to_string());
+    }
+
+    let proof = prove(k, &circuit, &public[0]).map_err(|e| format!("proving failed: {}", e))?;
+    fs::write(out_path, &proof).map_err(|e| format!("cannot write {}: {}", out_path, e))?;
+    println!("wrote a {} byte proof to {}", proof.len(), out_path);
+    Ok(())
+}
+
+fn verify_command(puzzle_path: &str, proof_path: &str) -> Result<(), String> {
+    let puzzle = Vec::<Vec<Fp>>::from(Grid(read_grid(puzzle_path)?));
+    let proof = fs::read(proof_path).map_err(|e| format!("cannot read {}: {}", proof_path, e))?;
+
+    let verifier =
+        SudokuVerifier::new(circuit_k(), &puzzle).map_err(|e| format!("keygen failed: {:?}", e))?;
+    verifier
+        .verify(&packed_instance(&puzzle)[0], &proof)
+        .map_err(|e| format!("proof does not verify: {}", e))?;
+    println!("proof verifies");
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.split_first() {
+        Some((command, rest)) if command == "prove" => flags(rest, &["puzzle", "solution", "out"])
+            .map(|values| prove_command(&values[0], &values[1], &values[2])),
+        Some((command, rest)) if command == "verify" => {
+            flags(rest, &["puzzle", "proof"]).map(|values| verify_command(&values[0], &values[1]))
+        }
+        _ => Err(String::new()),
+    };
+
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        Err(e) => {
+            if !e.is_empty() {
+                eprintln!("{}", e);
+            }
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::flags;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn flags_in_any_order() {
+        assert_eq!(
+            flags(&args("--proof p.bin --puzzle p.txt"), &["puzzle", "proof"]).unwrap(),
+            ["p.txt", "p.bin"]
+        );
+        assert!(flags(&args("--puzzle p.txt"), &["puzzle", "proof"]).is_err());
+        assert!(flags(&args("--puzzle a --puzzle b"), &["puzzle"]).is_err());
+        assert!(flags(&args("--puzzle"), &["puzzle"]).is_err());
+        assert!(flags(&args("--out x"), &["puzzle"]).is_err());
+    }
+}
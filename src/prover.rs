@@ -0,0 +1,1363 @@
+//! Real (non-mock) proving and verification over the Pasta curves with the
+//! IPA commitment scheme.
+//!
+//! These are the only curves and commitment scheme the crate proves with.
+//! The halo2 revision it builds on has neither a KZG backend nor the bn256
+//! curve, so there is no `prove_kzg`/`verify_kzg`; the circuits are generic
+//! over `F: FieldExt` already, so only this glue would need a second copy.
+//! For the same reason there is no Solidity verifier export:
+//! snark-verifier only generates EVM verifiers for KZG proofs over bn256.
+
+use halo2_proofs::{
+    dev::MockProver,
+    pasta::Fp,
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+#[cfg(feature = "verifier")]
+use halo2_proofs::{
+    pasta::EqAffine,
+    plonk::{keygen_vk, verify_proof, SingleVerifier, VerifyingKey},
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Challenge255},
+};
+#[cfg(feature = "prover")]
+use halo2_proofs::{
+    plonk::{create_proof, keygen_pk, ProvingKey},
+    transcript::Blake2bWrite,
+};
+#[cfg(feature = "prover")]
+use rand_chacha::ChaCha20Rng;
+#[cfg(feature = "prover")]
+use rand_core::{RngCore, SeedableRng};
+
+use crate::circuit::MyCircuit;
+#[cfg(feature = "prover")]
+use crate::io::packed_instance;
+#[cfg(feature = "prover")]
+use crate::solver::{uniqueness_certificate, UniquenessCertificate};
+#[cfg(feature = "verifier")]
+use crate::{circuit::circuit_commitment, error::SudokuError, io::check_instance};
+#[cfg(feature = "prover")]
+use std::io::Write;
+#[cfg(feature = "verifier")]
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+/// Borrows each instance column as a slice, the shape halo2's prover and
+/// verifier expect for a single circuit.
+#[cfg(feature = "verifier")]
+fn columns(instance: &[Vec<Fp>]) -> Vec<&[Fp]> {
+    instance.iter().map(|column| column.as_slice()).collect()
+}
+
+/// Loads commitment params previously written with `Params::write`, such as
+/// the output of a setup ceremony. I/O failures surface as
+/// `Error::Transcript`.
+#[cfg(feature = "verifier")]
+pub fn read_params(path: &Path) -> Result<Params<EqAffine>, Error> {
+    let file = File::open(path).map_err(Error::Transcript)?;
+    Params::read(&mut BufReader::new(file)).map_err(Error::Transcript)
+}
+
+/// The proving and verifying keys for `circuit` under `params`, e.g.
+/// `Params::new(k)` or the output of [`read_params`]. Any witness works for
+/// `circuit`, since keys only depend on its layout, which for the sudoku
+/// circuits includes which cells are given.
+#[cfg(feature = "prover")]
+pub fn keygen<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    circuit: &C,
+) -> Result<(ProvingKey<EqAffine>, VerifyingKey<EqAffine>), Error> {
+    let vk = keygen_vk(params, circuit)?;
+    let pk = keygen_pk(params, vk.clone(), circuit)?;
+    Ok((pk, vk))
+}
+
+/// Keys for `circuit` at size `2^k`, reusing the params cached at `path`
+/// when the file exists and was written for the same `k`, and otherwise
+/// generating them and (re)writing the file. The params are at `path`
+/// afterwards, for [`read_params`].
+///
+/// The halo2 revision this crate builds on cannot serialize proving or
+/// verifying keys, so only the params are cached and the keys are
+/// regenerated from them on every call. I/O failures surface as
+/// `Error::Transcript`.
+#[cfg(feature = "prover")]
+pub fn keygen_cached<C: Circuit<Fp>>(
+    k: u32,
+    circuit: &C,
+    path: &Path,
+) -> Result<(ProvingKey<EqAffine>, VerifyingKey<EqAffine>), SudokuError> {
+    let params = match cached_k(path) {
+        Some(cached) if cached == k => read_params(path)?,
+        _ => {
+            let params = Params::new(k);
+            let mut file = File::create(path).map_err(Error::Transcript)?;
+            params.write(&mut file).map_err(Error::Transcript)?;
+            params
+        }
+    };
+    Ok(keygen(&params, circuit)?)
+}
+
+/// The `k` that `Params::write` stores as the file's first four bytes, if
+/// `path` can be read.
+#[cfg(feature = "prover")]
+fn cached_k(path: &Path) -> Option<u32> {
+    let mut k = [0; 4];
+    File::open(path).ok()?.read_exact(&mut k).ok()?;
+    Some(u32::from_le_bytes(k))
+}
+
+/// Params of size `2^k` and a proving key for `circuit`.
+#[cfg(feature = "prover")]
+#[cfg_attr(
+    feature = "trace",
+    tracing::instrument(name = "keygen", skip_all, fields(n = N, k = k))
+)]
+fn setup<const N: usize, const BOX_ROWS: usize, const BOX_COLS: usize>(
+    k: u32,
+    circuit: &MyCircuit<Fp, N, BOX_ROWS, BOX_COLS>,
+) -> Result<(Params<EqAffine>, ProvingKey<EqAffine>), Error> {
+    let params = Params::new(k);
+    let (pk, _) = keygen(&params, circuit)?;
+    Ok((params, pk))
+}
+
+/// Generates params of size `2^k` and keys for `circuit`, then proves it
+/// against the puzzle `public`, packed row-major as by
+/// [`packed_instance`]. Params are derived from `k` alone, so a
+/// verifier can rebuild them with [`verify`]; the matching verifying key
+/// comes from `keygen_vk(&Params::new(k), circuit)`.
+///
+/// The proof is zero-knowledge: halo2 keeps the last
+/// `blinding_factors() + 1` rows of every advice column out of the layout
+/// and fills them with random values, so the commitments and openings of
+/// the columns holding the private solution reveal nothing about it. The
+/// randomness comes from a ChaCha20 stream seeded by the OS, as for
+/// [`prove_with_params`]; a `k` below [`min_k`] fails with
+/// `Error::NotEnoughRowsAvailable` rather than eating into those rows.
+///
+/// A clue in `public` that is neither zero nor a digit fails with
+/// [`SudokuError::ValueOutOfRange`] before any proving work.
+#[cfg(feature = "prover")]
+#[cfg_attr(
+    feature = "trace",
+    tracing::instrument(name = "prove", skip_all, fields(n = N, k = k))
+)]
+pub fn prove<const N: usize, const BOX_ROWS: usize, const BOX_COLS: usize>(
+    k: u32,
+    circuit: &MyCircuit<Fp, N, BOX_ROWS, BOX_COLS>,
+    public: &[Fp],
+) -> Result<Vec<u8>, SudokuError> {
+    check_instance(public, N)?;
+    let (params, pk) = setup(k, circuit)?;
+    Ok(prove_with_params(
+        &params,
+        &pk,
+        circuit.clone(),
+        &[public.to_vec()],
+    )?)
+}
+
+/// Proves a solution to `clues` with [`prove`] and, from an exhaustive
+/// search, a [`UniquenessCertificate`] that it is the only one. The proof is
+/// over the puzzle's packed instance at [`min_k`] of its circuit.
+///
+/// Uniqueness is *not* part of the SNARK: the proof only shows that the
+/// prover knows some solution. The certificate is checked separately, on
+/// the host, with [`verify_uniqueness`](crate::solver::verify_uniqueness).
+/// A puzzle with several solutions fails with [`SudokuError::NotUnique`]
+/// and one without any with [`SudokuError::Unsolvable`].
+#[cfg(feature = "prover")]
+pub fn prove_uniqueness(
+    clues: &[[u8; 9]; 9],
+) -> Result<(Vec<u8>, UniquenessCertificate), SudokuError> {
+    let certificate = uniqueness_certificate(clues)?;
+    let (circuit, instance) = MyCircuit::<Fp>::build(clues)?;
+    let proof = prove(min_k(&circuit), &circuit, &instance[0])?;
+    Ok((proof, certificate))
+}
+
+/// Like [`prove`], but writes the proof straight to `writer`, such as a file
+/// or socket, as the transcript produces it.
+#[cfg(feature = "prover")]
+#[cfg_attr(
+    feature = "trace",
+    tracing::instrument(name = "prove", skip_all, fields(n = N, k = k))
+)]
+pub fn prove_to<const N: usize, const BOX_ROWS: usize, const BOX_COLS: usize, W: Write>(
+    k: u32,
+    circuit: &MyCircuit<Fp, N, BOX_ROWS, BOX_COLS>,
+    public: &[Fp],
+    writer: W,
+) -> Result<(), SudokuError> {
+    check_instance(public, N)?;
+    let (params, pk) = setup(k, circuit)?;
+    prove_to_writer(&params, &pk, circuit.clone(), &[public.to_vec()], writer)?;
+    Ok(())
+}
+
+/// Like [`verify`], but reads the proof from `reader` as the transcript
+/// consumes it. Read failures surface as `Error::Transcript`.
+#[cfg(feature = "verifier")]
+#[cfg_attr(
+    feature = "trace",
+    tracing::instrument(name = "verify", skip_all, fields(cells = public.len(), k = k))
+)]
+pub fn verify_from<R: Read>(
+    k: u32,
+    vk: &VerifyingKey<EqAffine>,
+    public: &[Fp],
+    reader: R,
+) -> Result<(), SudokuError> {
+    check_instance(public, board_size(public))?;
+    let params = Params::new(k);
+    verify_reader(&params, vk, reader, &[public.to_vec()])?;
+    Ok(())
+}
+
+/// The side of the smallest board whose packed instance holds `public`.
+#[cfg(feature = "verifier")]
+fn board_size(public: &[Fp]) -> usize {
+    (1..).find(|n| n * n >= public.len()).unwrap()
+}
+
+/// Verifies a proof made by [`prove`] with the same `k`. As for [`prove`],
+/// a clue out of range fails with [`SudokuError::ValueOutOfRange`] before
+/// the proof is read.
+#[cfg(feature = "verifier")]
+#[cfg_attr(
+    feature = "trace",
+    tracing::instrument(name = "verify", skip_all, fields(cells = public.len(), k = k))
+)]
+pub fn verify(
+    k: u32,
+    vk: &VerifyingKey<EqAffine>,
+    public: &[Fp],
+    proof: &[u8],
+) -> Result<(), SudokuError> {
+    check_instance(public, board_size(public))?;
+    let params = Params::new(k);
+    verify_with_params(&params, vk, proof, &[public.to_vec()])?;
+    Ok(())
+}
+
+/// The smallest `k` that `circuit` fits in, found by running MockProver at
+/// increasing `k` until synthesis stops running out of rows. Only the row
+/// count is checked, not the witness, so an unsatisfied circuit still gets
+/// an answer. Panics if synthesis fails for any other reason.
+pub fn min_k<const N: usize, const BOX_ROWS: usize, const BOX_COLS: usize>(
+    circuit: &MyCircuit<Fp, N, BOX_ROWS, BOX_COLS>,
+) -> u32 {
+    let mut cs = ConstraintSystem::<Fp>::default();
+    MyCircuit::<Fp, N, BOX_ROWS, BOX_COLS>::configure(&mut cs);
+    // MockProver reserves `blinding_factors() + 1` rows at the end, so any
+    // smaller domain has no usable rows at all.
+    let reserved = cs.blinding_factors() + 1;
+    let instance = vec![vec![Fp::zero(); N * N]];
+
+    let mut k = 1;
+    while 1 << k <= reserved {
+        k += 1;
+    }
+    loop {
+        match MockProver::run(k, circuit, instance.clone()) {
+            Ok(_) => return k,
+            Err(Error::NotEnoughRowsAvailable { .. }) | Err(Error::InstanceTooLarge) => k += 1,
+            Err(e) => panic!("synthesis failed at k = {}: {:?}", k, e),
+        }
+    }
+}
+
+/// Whether `circuit` satisfies every constraint for the instance `public`
+/// at size `2^k`, as a bool for solution checkers and test oracles. Public
+/// input of the wrong shape, or a `k` too small for the board, gives `false`
+/// instead of an error or a panic.
+pub fn is_satisfied<const N: usize, const BOX_ROWS: usize, const BOX_COLS: usize>(
+    k: u32,
+    circuit: &MyCircuit<Fp, N, BOX_ROWS, BOX_COLS>,
+    public: &[Vec<Fp>],
+) -> bool {
+    let mut cs = ConstraintSystem::<Fp>::default();
+    MyCircuit::<Fp, N, BOX_ROWS, BOX_COLS>::configure(&mut cs);
+    if public.len() != cs.num_instance_columns() || 1 << k <= cs.blinding_factors() + 1 {
+        return false;
+    }
+
+    MockProver::run(k, circuit, public.to_vec()).map_or(false, |prover| prover.verify().is_ok())
+}
+
+/// Creates a proof for `circuit` against already generated params and keys.
+///
+/// Proofs are blinded with fresh randomness, so proving the same statement
+/// twice gives different bytes that both verify; see [`prove_deterministic`]
+/// for reproducible output.
+#[cfg(feature = "prover")]
+pub fn prove_with_params<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: C,
+    instance: &[Vec<Fp>],
+) -> Result<Vec<u8>, Error> {
+    let mut proof = vec![];
+    prove_to_writer(params, pk, circuit, instance, &mut proof)?;
+    Ok(proof)
+}
+
+/// Like [`prove_with_params`], but the transcript writes each proof element
+/// straight to `writer` as it is produced, so batch jobs writing to disk
+/// never hold a whole proof in memory. Write failures surface as
+/// `Error::Transcript`.
+#[cfg(feature = "prover")]
+pub fn prove_to_writer<C: Circuit<Fp>, W: Write>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: C,
+    instance: &[Vec<Fp>],
+    writer: W,
+) -> Result<(), Error> {
+    prove_with_rng(
+        params,
+        pk,
+        circuit,
+        instance,
+        writer,
+        ChaCha20Rng::from_entropy(),
+    )
+}
+
+/// Like [`prove_with_params`], but draws the blinding randomness from a
+/// ChaCha20 stream seeded with `seed`, so the same inputs always give the
+/// same proof bytes. Useful for reproducible artifacts and golden files; a
+/// seed reused across different witnesses lets a verifier compare their
+/// blinding, so never use one for proofs that need to stay zero-knowledge.
+#[cfg(feature = "prover")]
+pub fn prove_deterministic<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: C,
+    instance: &[Vec<Fp>],
+    seed: [u8; 32],
+) -> Result<Vec<u8>, Error> {
+    let mut proof = vec![];
+    let rng = ChaCha20Rng::from_seed(seed);
+    prove_with_rng(params, pk, circuit, instance, &mut proof, rng)?;
+    Ok(proof)
+}
+
+#[cfg(feature = "prover")]
+fn prove_with_rng<C: Circuit<Fp>, W: Write, R: RngCore>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: C,
+    instance: &[Vec<Fp>],
+    writer: W,
+    rng: R,
+) -> Result<(), Error> {
+    let columns = columns(instance);
+    let mut transcript = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(writer);
+    create_proof(
+        params,
+        pk,
+        &[circuit],
+        &[&columns[..]],
+        rng,
+        &mut transcript,
+    )?;
+    transcript.finalize();
+    Ok(())
+}
+
+/// Verifies `proof` against already loaded params.
+#[cfg(feature = "verifier")]
+pub fn verify_with_params(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proof: &[u8],
+    instance: &[Vec<Fp>],
+) -> Result<(), Error> {
+    verify_reader(params, vk, proof, instance)
+}
+
+#[cfg(feature = "verifier")]
+fn verify_reader<R: Read>(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    reader: R,
+    instance: &[Vec<Fp>],
+) -> Result<(), Error> {
+    let columns = columns(instance);
+    let strategy = SingleVerifier::new(params);
+    let mut transcript = Blake2bRead::<_, EqAffine, Challenge255<_>>::init(reader);
+    verify_proof(params, vk, strategy, &[&columns[..]], &mut transcript)
+}
+
+/// Like [`verify_with_params`], for public inputs supplied as one flat
+/// slice, as verifiers in other ecosystems pass them. The circuit has a single
+/// instance column holding the `N x N` puzzle row-major, so the slice is that
+/// column as is; a length other than `N * N` fails with
+/// `Error::InvalidInstances`.
+#[cfg(feature = "verifier")]
+pub fn verify_flat<const N: usize>(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proof: &[u8],
+    instances: &[Fp],
+) -> Result<(), Error> {
+    if instances.len() != N * N {
+        return Err(Error::InvalidInstances);
+    }
+    verify_with_params(params, vk, proof, &[instances.to_vec()])
+}
+
+/// Verifies `proof` only if the circuit type `C`, whose keys `vk` must come
+/// from, still configures the constraint system pinned by `commitment` (see
+/// [`circuit_commitment`]). A mismatch fails with
+/// `Error::ConstraintSystemFailure` before any proof checking.
+#[cfg(feature = "verifier")]
+pub fn verify_with_commitment<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proof: &[u8],
+    instance: &[Vec<Fp>],
+    commitment: [u8; 32],
+) -> Result<(), Error> {
+    if circuit_commitment::<Fp, C>() != commitment {
+        return Err(Error::ConstraintSystemFailure);
+    }
+    verify_with_params(params, vk, proof, instance)
+}
+
+/// The first bytes of every [`Proof`] envelope.
+#[cfg(feature = "verifier")]
+const PROOF_MAGIC: [u8; 4] = *b"SDKP";
+/// The envelope layout [`Proof::to_bytes`] writes. Bumped whenever the
+/// header changes, so older crates reject newer proofs instead of misreading
+/// them.
+#[cfg(feature = "verifier")]
+const PROOF_FORMAT: u8 = 1;
+/// The only curve and commitment scheme the crate proves with: Pasta, with
+/// IPA commitments.
+#[cfg(feature = "verifier")]
+const CURVE_PASTA_IPA: u8 = 1;
+/// Magic, format, `k`, curve and circuit digest.
+#[cfg(feature = "verifier")]
+const PROOF_HEADER_LEN: usize = 4 + 1 + 4 + 1 + 32;
+
+/// A proof's transcript bytes together with what is needed to check them:
+/// the `k` it was made at and the [`circuit_commitment`] of its circuit.
+///
+/// [`Self::to_bytes`] writes the magic bytes `SDKP`, a format version byte,
+/// `k` as a little-endian `u32`, a curve id byte, the 32-byte circuit digest
+/// and then the transcript; [`Self::from_bytes`] rejects anything else.
+#[cfg(feature = "verifier")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof {
+    pub k: u32,
+    pub circuit: [u8; 32],
+    pub transcript: Vec<u8>,
+}
+
+#[cfg(feature = "verifier")]
+impl Proof {
+    /// Wraps `transcript`, a proof of circuit type `C` made at `k`, e.g. the
+    /// output of [`prove_with_params`].
+    pub fn new<C: Circuit<Fp>>(k: u32, transcript: Vec<u8>) -> Self {
+        Self {
+            k,
+            circuit: circuit_commitment::<Fp, C>(),
+            transcript,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(PROOF_HEADER_LEN + self.transcript.len());
+        bytes.extend_from_slice(&PROOF_MAGIC);
+        bytes.push(PROOF_FORMAT);
+        bytes.extend_from_slice(&self.k.to_le_bytes());
+        bytes.push(CURVE_PASTA_IPA);
+        bytes.extend_from_slice(&self.circuit);
+        bytes.extend_from_slice(&self.transcript);
+        bytes
+    }
+
+    /// Parses an envelope written by [`Self::to_bytes`]. A short header,
+    /// other magic bytes, or a format version or curve this crate does not
+    /// know fail with [`SudokuError::InvalidProof`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SudokuError> {
+        let invalid = |reason| SudokuError::InvalidProof { reason };
+        if bytes.len() < PROOF_HEADER_LEN {
+            return Err(invalid("shorter than the header"));
+        }
+        let (header, transcript) = bytes.split_at(PROOF_HEADER_LEN);
+        if header[..4] != PROOF_MAGIC {
+            return Err(invalid("not a sudoku proof"));
+        }
+        if header[4] != PROOF_FORMAT {
+            return Err(invalid("unsupported format version"));
+        }
+        if header[9] != CURVE_PASTA_IPA {
+            return Err(invalid("unsupported curve"));
+        }
+
+        let mut k = [0; 4];
+        k.copy_from_slice(&header[5..9]);
+        let mut circuit = [0; 32];
+        circuit.copy_from_slice(&header[10..]);
+        Ok(Self {
+            k: u32::from_le_bytes(k),
+            circuit,
+            transcript: transcript.to_vec(),
+        })
+    }
+
+    /// Verifies the transcript at `k`, the size `vk` was generated for, but
+    /// only if the envelope was made at that `k` for circuit type `C`;
+    /// otherwise fails with [`SudokuError::InvalidProof`] before any proof
+    /// checking. The envelope's own `k` is never trusted to size the params,
+    /// as a crafted header could ask for an arbitrarily large setup.
+    pub fn verify<C: Circuit<Fp>>(
+        &self,
+        k: u32,
+        vk: &VerifyingKey<EqAffine>,
+        instance: &[Vec<Fp>],
+    ) -> Result<(), SudokuError> {
+        if self.k != k {
+            return Err(SudokuError::InvalidProof {
+                reason: "made at another k",
+            });
+        }
+        if self.circuit != circuit_commitment::<Fp, C>() {
+            return Err(SudokuError::InvalidProof {
+                reason: "made for another circuit",
+            });
+        }
+        let params = Params::new(k);
+        verify_with_params(&params, vk, &self.transcript, instance)?;
+        Ok(())
+    }
+}
+
+/// Verifies `proof` with params loaded from `path` instead of regenerating
+/// them, matching deployments where params come from a ceremony. The params
+/// must be the ones `vk` was generated with.
+#[cfg(feature = "verifier")]
+pub fn verify_with_params_file(
+    path: &Path,
+    vk: &VerifyingKey<EqAffine>,
+    proof: &[u8],
+    instance: &[Vec<Fp>],
+) -> Result<(), Error> {
+    let params = read_params(path)?;
+    verify_with_params(&params, vk, proof, instance)
+}
+
+/// A witness with the right shape for the puzzle `public` but arbitrary
+/// values: one in every blank, zero (copy from the instance) at every given.
+/// Enough for keygen, which only sees which cells are given.
+pub(crate) fn placeholder(public: &[Vec<Fp>]) -> MyCircuit<Fp> {
+    let solution = public
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&given| {
+                    if given == Fp::zero() {
+                        Fp::one()
+                    } else {
+                        Fp::zero()
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    MyCircuit {
+        solution,
+        chunked: false,
+    }
+}
+
+/// Params and keys for proving solutions to puzzles with one layout of
+/// givens, kept around so a server pays for setup once.
+///
+/// The keys depend on which cells are given, since those are copied from the
+/// instance, so every proof uses the puzzle the prover was built for.
+#[cfg(feature = "prover")]
+pub struct SudokuProver {
+    k: u32,
+    params: Params<EqAffine>,
+    pk: ProvingKey<EqAffine>,
+    public: Vec<Vec<Fp>>,
+}
+
+#[cfg(feature = "prover")]
+impl SudokuProver {
+    /// Generates params of size `2^k` and keys for the puzzle `public`.
+    pub fn new(k: u32, public: Vec<Vec<Fp>>) -> Result<Self, Error> {
+        let (params, pk) = setup(k, &placeholder(&public))?;
+        Ok(Self {
+            k,
+            params,
+            pk,
+            public,
+        })
+    }
+
+    pub fn params(&self) -> &Params<EqAffine> {
+        &self.params
+    }
+
+    pub fn vk(&self) -> &VerifyingKey<EqAffine> {
+        self.pk.get_vk()
+    }
+
+    /// Creates and discards one proof of a placeholder witness, so the first
+    /// real [`Self::prove`] does not pay for cold allocations and thread pool
+    /// start-up. halo2 already computes the evaluation domain's FFT tables
+    /// during keygen, in [`Self::new`].
+    pub fn warm_up(&self) -> Result<(), Error> {
+        prove_with_params(
+            &self.params,
+            &self.pk,
+            placeholder(&self.public),
+            &packed_instance(&self.public),
+        )
+        .map(drop)
+    }
+
+    /// Proves that `solution`, zero at the givens, solves the prover's puzzle.
+    /// A `solution` that is not 9x9 fails with
+    /// [`SudokuError::BadDimensions`] before any proving work.
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(name = "prove", skip_all, fields(n = 9, k = self.k))
+    )]
+    pub fn prove(&self, solution: Vec<Vec<Fp>>) -> Result<Vec<u8>, SudokuError> {
+        let circuit = MyCircuit::new(solution)?;
+        Ok(prove_with_params(
+            &self.params,
+            &self.pk,
+            circuit,
+            &packed_instance(&self.public),
+        )?)
+    }
+
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(name = "verify", skip_all, fields(n = 9, k = self.k))
+    )]
+    pub fn verify(&self, proof: &[u8]) -> Result<(), Error> {
+        verify_with_params(
+            &self.params,
+            self.vk(),
+            proof,
+            &packed_instance(&self.public),
+        )
+    }
+}
+
+/// Params and a verifying key built once and reused for every proof, for
+/// services that verify many of them.
+///
+/// Like [`SudokuProver`]'s keys, the verifying key depends on which cells
+/// of the puzzle are given, so it is built from a puzzle with the same
+/// layout of givens as the proofs it checks.
+#[cfg(feature = "verifier")]
+pub struct SudokuVerifier {
+    params: Params<EqAffine>,
+    vk: VerifyingKey<EqAffine>,
+}
+
+#[cfg(feature = "verifier")]
+impl SudokuVerifier {
+    /// Generates params of size `2^k` and the verifying key for puzzles
+    /// laid out like `puzzle`, zero for blanks.
+    pub fn new(k: u32, puzzle: &[Vec<Fp>]) -> Result<Self, Error> {
+        let params = Params::new(k);
+        let vk = keygen_vk(&params, &placeholder(puzzle))?;
+        Ok(Self { params, vk })
+    }
+
+    pub fn vk(&self) -> &VerifyingKey<EqAffine> {
+        &self.vk
+    }
+
+    /// Verifies a proof against the puzzle `public`, packed row-major as by
+    /// [`packed_instance`](crate::io::packed_instance). As for [`verify`], a
+    /// clue out of range fails with [`SudokuError::ValueOutOfRange`] before
+    /// the proof is read.
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(name = "verify", skip_all, fields(cells = public.len()))
+    )]
+    pub fn verify(&self, public: &[Fp], proof: &[u8]) -> Result<(), SudokuError> {
+        check_instance(public, 9)?;
+        verify_with_params(&self.params, &self.vk, proof, &[public.to_vec()])?;
+        Ok(())
+    }
+}
+
+/// A proof of the 4x4 example, kept in the repository so verifier-only
+/// builds have one to check. A default-features `cargo test` fails once it
+/// is stale or missing; write it with
+/// `SUDOKU_REGENERATE_FIXTURES=1 cargo test --lib pre_generated_proof_is_current`
+/// (see `testdata/README.md`).
+#[cfg(all(test, feature = "verifier"))]
+const FOUR_BY_FOUR_PROOF: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/four_by_four.proof");
+
+/// The 4x4 example puzzle and the witness of its solution, for
+/// [`FOUR_BY_FOUR_PROOF`].
+#[cfg(all(test, feature = "verifier"))]
+fn four_by_four() -> (Vec<Fp>, MyCircuit<Fp, 4, 2>) {
+    use crate::io::to_field_grid;
+
+    let public_input = to_field_grid::<Fp>(vec![
+        vec![1, 0, 0, 0],
+        vec![0, 4, 0, 0],
+        vec![0, 0, 4, 0],
+        vec![0, 0, 0, 1],
+    ])
+    .concat();
+    let circuit = MyCircuit::<Fp, 4, 2>::sized(to_field_grid(vec![
+        vec![0, 2, 3, 4],
+        vec![3, 0, 1, 2],
+        vec![2, 1, 0, 3],
+        vec![4, 3, 2, 0],
+    ]))
+    .unwrap();
+    (public_input, circuit)
+}
+
+#[cfg(all(test, feature = "verifier", not(feature = "prover")))]
+mod verifier_tests {
+    use super::{four_by_four, verify, FOUR_BY_FOUR_PROOF};
+    use halo2_proofs::{plonk::keygen_vk, poly::commitment::Params};
+    use std::fs;
+
+    #[test]
+    fn verifies_a_pre_generated_proof() {
+        let k = 5;
+        let (public_input, circuit) = four_by_four();
+        let proof =
+            fs::read(FOUR_BY_FOUR_PROOF).expect("the committed 4x4 proof fixture is missing");
+
+        let vk = keygen_vk(&Params::new(k), &circuit).unwrap();
+        assert!(verify(k, &vk, &public_input, &proof).is_ok());
+
+        let mut tampered = proof;
+        tampered[0] ^= 1;
+        assert!(verify(k, &vk, &public_input, &tampered).is_err());
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::{
+        four_by_four, is_satisfied, keygen, keygen_cached, min_k, prove, prove_deterministic,
+        prove_to, prove_to_writer, prove_uniqueness, prove_with_params, read_params, verify,
+        verify_flat, verify_from, verify_with_commitment, verify_with_params,
+        verify_with_params_file, Proof, SudokuProver, SudokuVerifier, FOUR_BY_FOUR_PROOF,
+    };
+    use crate::{
+        circuit::{circuit_commitment, MyCircuit},
+        error::SudokuError,
+        fixtures::{example_arrays, example_grids},
+        io::{packed_instance, solution_witness, to_field_grid, Grid},
+        solver::{
+            generate_puzzle, has_unique_solution, solve_puzzle, validate_solution,
+            verify_uniqueness, UniquenessCertificate,
+        },
+    };
+    use halo2_proofs::{
+        pasta::{EqAffine, Fp},
+        plonk::{keygen_pk, keygen_vk, Error, VerifyingKey},
+        poly::commitment::Params,
+    };
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+    use std::{
+        env,
+        fs::{self, File},
+        io::Cursor,
+        path::Path,
+    };
+
+    /// Panics unless both proofs verify for `instance`. Two proofs of the
+    /// same statement normally differ byte for byte, so tests comparing
+    /// proofs use this instead of `assert_eq!`.
+    fn assert_both_verify(
+        params: &Params<EqAffine>,
+        vk: &VerifyingKey<EqAffine>,
+        proof_a: &[u8],
+        proof_b: &[u8],
+        instance: &[Vec<Fp>],
+    ) {
+        for (name, proof) in [("first", proof_a), ("second", proof_b)] {
+            if let Err(e) = verify_with_params(params, vk, proof, instance) {
+                panic!("{} proof does not verify: {:?}", name, e);
+            }
+        }
+    }
+
+    #[test]
+    fn keygen_cache_round_trip() {
+        let (public_input, circuit) = four_by_four();
+        let instance = [public_input];
+
+        let path = std::env::temp_dir().join(format!(
+            "halo2-sudoku-keygen-cache-{}.bin",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+
+        // a stale cache for another k is replaced
+        keygen_cached(6, &circuit, &path).unwrap();
+        let (pk, _) = keygen_cached(5, &circuit, &path).unwrap();
+        let params = read_params(&path).unwrap();
+        let proof = prove_with_params(&params, &pk, circuit.clone(), &instance).unwrap();
+
+        // the second call reads the params back
+        let (_, vk) = keygen_cached(5, &circuit, &path).unwrap();
+        let params = read_params(&path).unwrap();
+        let result = verify_with_params(&params, &vk, &proof, &instance);
+
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn verify_against_params_file() {
+        let k = 7;
+
+        let (public_grid, solution) = example_grids();
+
+        let public_input = packed_instance(&to_field_grid(public_grid));
+        let circuit = MyCircuit::<Fp> {
+            solution: to_field_grid(solution),
+            chunked: false,
+        };
+
+        let params: Params<EqAffine> = Params::new(k);
+        let path = std::env::temp_dir().join(format!(
+            "halo2-sudoku-params-{}-{}.bin",
+            k,
+            std::process::id()
+        ));
+        params.write(&mut File::create(&path).unwrap()).unwrap();
+
+        let vk = keygen_vk(&params, &circuit).unwrap();
+        let pk = keygen_pk(&params, vk.clone(), &circuit).unwrap();
+        let proof = prove_with_params(&params, &pk, circuit, &public_input).unwrap();
+
+        let result = verify_with_params_file(&path, &vk, &proof, &public_input);
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn prove_into_buffer() {
+        let k = 7;
+
+        let (public_grid, solution) = example_grids();
+
+        let public_input = packed_instance(&to_field_grid(public_grid));
+        let circuit = MyCircuit::<Fp> {
+            solution: to_field_grid(solution),
+            chunked: false,
+        };
+
+        let params: Params<EqAffine> = Params::new(k);
+        let vk = keygen_vk(&params, &circuit).unwrap();
+        let pk = keygen_pk(&params, vk.clone(), &circuit).unwrap();
+
+        let mut buffer = Vec::new();
+        prove_to_writer(&params, &pk, circuit, &public_input, &mut buffer).unwrap();
+
+        assert!(!buffer.is_empty());
+        assert!(verify_with_params(&params, &vk, &buffer, &public_input).is_ok());
+
+        let commitment = circuit_commitment::<Fp, MyCircuit<Fp>>();
+        assert!(verify_with_commitment::<MyCircuit<Fp>>(
+            &params,
+            &vk,
+            &buffer,
+            &public_input,
+            commitment
+        )
+        .is_ok());
+
+        let mut wrong = commitment;
+        wrong[0] ^= 1;
+        assert!(verify_with_commitment::<MyCircuit<Fp>>(
+            &params,
+            &vk,
+            &buffer,
+            &public_input,
+            wrong
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn warm_up_then_prove() {
+        let (public_grid, solution) = example_grids();
+
+        let prover = SudokuProver::new(7, to_field_grid(public_grid)).unwrap();
+        prover.warm_up().unwrap();
+
+        let proof = prover.prove(to_field_grid(solution)).unwrap();
+        assert!(prover.verify(&proof).is_ok());
+    }
+
+    #[test]
+    fn verifier_is_reused_across_proofs() {
+        let (puzzle, full) = generate_puzzle(&mut ChaCha20Rng::seed_from_u64(300), 30);
+        let public = Vec::<Vec<Fp>>::from(Grid(puzzle));
+        let instance = packed_instance(&public);
+
+        let prover = SudokuProver::new(7, public.clone()).unwrap();
+        let witness: Vec<Vec<Fp>> = solution_witness(&puzzle, &full);
+        let first = prover.prove(witness.clone()).unwrap();
+        let second = prover.prove(witness).unwrap();
+        assert_ne!(first, second);
+
+        let verifier = SudokuVerifier::new(7, &public).unwrap();
+        assert!(verifier.verify(&instance[0], &first).is_ok());
+        assert!(verifier.verify(&instance[0], &second).is_ok());
+
+        let mut tampered = second;
+        tampered[0] ^= 1;
+        assert!(verifier.verify(&instance[0], &tampered).is_err());
+    }
+
+    #[test]
+    fn deterministic_proofs_repeat() {
+        let k = 7;
+
+        let (public_grid, solution) = example_grids();
+
+        let public_input = packed_instance(&to_field_grid(public_grid));
+        let circuit = || MyCircuit::<Fp> {
+            solution: to_field_grid(solution.clone()),
+            chunked: false,
+        };
+
+        let params: Params<EqAffine> = Params::new(k);
+        let vk = keygen_vk(&params, &circuit()).unwrap();
+        let pk = keygen_pk(&params, vk.clone(), &circuit()).unwrap();
+
+        let prove = |seed| prove_deterministic(&params, &pk, circuit(), &public_input, seed);
+        let first = prove([7; 32]).unwrap();
+        assert_eq!(first, prove([7; 32]).unwrap());
+
+        let other_seed = prove([8; 32]).unwrap();
+        assert_ne!(first, other_seed);
+        assert_both_verify(&params, &vk, &first, &other_seed, &public_input);
+
+        let random = prove_with_params(&params, &pk, circuit(), &public_input).unwrap();
+        assert_both_verify(&params, &vk, &first, &random, &public_input);
+    }
+
+    #[test]
+    fn packed_and_nine_column_instances_agree() {
+        let k = 7;
+
+        let (public_grid, solution) = example_grids();
+
+        let grid = to_field_grid(public_grid);
+        let public_input = packed_instance(&grid);
+        let circuit = MyCircuit::<Fp> {
+            solution: to_field_grid(solution),
+            chunked: false,
+        };
+
+        let params: Params<EqAffine> = Params::new(k);
+        let vk = keygen_vk(&params, &circuit).unwrap();
+        let pk = keygen_pk(&params, vk.clone(), &circuit).unwrap();
+        let proof = prove_with_params(&params, &pk, circuit, &public_input).unwrap();
+
+        // The old layout gave each grid row its own instance column; read
+        // back to back, those columns are exactly the packed column.
+        let mut tampered = grid.clone();
+        tampered[0][2] += Fp::one();
+
+        for nested in [grid, tampered] {
+            let flat: Vec<Fp> = nested.iter().flatten().copied().collect();
+            let packed = packed_instance(&nested);
+            assert_eq!(packed, vec![flat.clone()]);
+            assert_eq!(
+                verify_flat::<9>(&params, &vk, &proof, &flat).is_ok(),
+                verify_with_params(&params, &vk, &proof, &packed).is_ok()
+            );
+        }
+        assert!(verify_with_params(&params, &vk, &proof, &public_input).is_ok());
+
+        let flat = vec![Fp::zero(); 80];
+        assert!(matches!(
+            verify_flat::<9>(&params, &vk, &proof, &flat),
+            Err(Error::InvalidInstances)
+        ));
+
+        // other board sizes check against their own cell count
+        let k = 5;
+        let (public_input, circuit) = four_by_four();
+        let params: Params<EqAffine> = Params::new(k);
+        let (pk, vk) = keygen(&params, &circuit).unwrap();
+        let proof = prove_with_params(&params, &pk, circuit, &[public_input.clone()]).unwrap();
+        assert!(verify_flat::<4>(&params, &vk, &proof, &public_input).is_ok());
+        assert!(matches!(
+            verify_flat::<9>(&params, &vk, &proof, &public_input),
+            Err(Error::InvalidInstances)
+        ));
+    }
+
+    #[test]
+    fn prove_and_verify_round_trip() {
+        let k = 7;
+
+        let (public_grid, solution) = example_grids();
+
+        let public_input = to_field_grid::<Fp>(public_grid).concat();
+        let circuit = MyCircuit::new(to_field_grid(solution)).unwrap();
+
+        let proof = prove(k, &circuit, &public_input).unwrap();
+        let vk = keygen_vk(&Params::new(k), &circuit).unwrap();
+        assert!(verify(k, &vk, &public_input, &proof).is_ok());
+
+        let mut tampered = proof.clone();
+        let middle = tampered.len() / 2;
+        tampered[middle] ^= 1;
+        assert!(verify(k, &vk, &public_input, &tampered).is_err());
+    }
+
+    #[test]
+    fn prove_four_by_four() {
+        let k = 5;
+
+        let (public_input, circuit) = four_by_four();
+
+        let proof = prove(k, &circuit, &public_input).unwrap();
+        let vk = keygen_vk(&Params::new(k), &circuit).unwrap();
+        assert!(verify(k, &vk, &public_input, &proof).is_ok());
+    }
+
+    #[test]
+    fn keygen_then_prove_and_verify() {
+        let (public_input, circuit) = four_by_four();
+        let instance = [public_input];
+        let params = Params::new(5);
+        let (pk, vk) = keygen(&params, &circuit).unwrap();
+
+        let proof = prove_with_params(&params, &pk, circuit, &instance).unwrap();
+        assert!(verify_with_params(&params, &vk, &proof, &instance).is_ok());
+
+        let mut other = instance.clone();
+        other[0][0] = Fp::from(2);
+        assert!(verify_with_params(&params, &vk, &proof, &other).is_err());
+    }
+
+    #[test]
+    fn proof_envelope_round_trip() {
+        let k = 5;
+        let (public_input, circuit) = four_by_four();
+        let instance = [public_input];
+        let params = Params::new(k);
+        let (pk, vk) = keygen(&params, &circuit).unwrap();
+        let transcript = prove_with_params(&params, &pk, circuit, &instance).unwrap();
+
+        let proof = Proof::new::<MyCircuit<Fp, 4, 2>>(k, transcript.clone());
+        let bytes = proof.to_bytes();
+        assert_eq!(&bytes[..4], b"SDKP");
+        assert_eq!(bytes.len(), 42 + transcript.len());
+
+        let read = Proof::from_bytes(&bytes).unwrap();
+        assert_eq!(read, proof);
+        assert!(read
+            .verify::<MyCircuit<Fp, 4, 2>>(k, &vk, &instance)
+            .is_ok());
+        assert!(matches!(
+            read.verify::<MyCircuit<Fp>>(k, &vk, &instance),
+            Err(SudokuError::InvalidProof { .. })
+        ));
+
+        // a header asking for an oversized setup is rejected before any
+        // params are generated
+        let mut oversized = bytes.clone();
+        oversized[5..9].copy_from_slice(&40u32.to_le_bytes());
+        let oversized = Proof::from_bytes(&oversized).unwrap();
+        assert_eq!(oversized.k, 40);
+        assert!(matches!(
+            oversized.verify::<MyCircuit<Fp, 4, 2>>(k, &vk, &instance),
+            Err(SudokuError::InvalidProof { .. })
+        ));
+
+        // a header from a future format, another curve, or cut short
+        for (index, value) in [(0, b'X'), (4, 2), (9, 7)] {
+            let mut changed = bytes.clone();
+            changed[index] = value;
+            assert!(matches!(
+                Proof::from_bytes(&changed),
+                Err(SudokuError::InvalidProof { .. })
+            ));
+        }
+        assert!(Proof::from_bytes(&bytes[..41]).is_err());
+    }
+
+    #[test]
+    fn pre_generated_proof_is_current() {
+        let k = 5;
+        let (public_input, circuit) = four_by_four();
+        let params = Params::new(k);
+        let vk = keygen_vk(&params, &circuit).unwrap();
+        let pk = keygen_pk(&params, vk.clone(), &circuit).unwrap();
+        let proof =
+            prove_deterministic(&params, &pk, circuit, &[public_input.clone()], [0; 32]).unwrap();
+
+        if env::var_os("SUDOKU_REGENERATE_FIXTURES").is_some() {
+            fs::create_dir_all(Path::new(FOUR_BY_FOUR_PROOF).parent().unwrap()).unwrap();
+            fs::write(FOUR_BY_FOUR_PROOF, &proof).unwrap();
+        }
+        let stored = fs::read(FOUR_BY_FOUR_PROOF).unwrap_or_else(|_| {
+            panic!(
+                "{} is missing; rerun with SUDOKU_REGENERATE_FIXTURES=1",
+                FOUR_BY_FOUR_PROOF
+            )
+        });
+        assert_eq!(
+            stored, proof,
+            "{} is stale; rerun with SUDOKU_REGENERATE_FIXTURES=1",
+            FOUR_BY_FOUR_PROOF
+        );
+        assert!(verify(k, &vk, &public_input, &proof).is_ok());
+    }
+
+    #[test]
+    fn generated_puzzle_end_to_end() {
+        let k = 7;
+        let mut rng = ChaCha20Rng::seed_from_u64(289);
+        let (clues, full) = generate_puzzle(&mut rng, 30);
+        assert_eq!(clues.iter().flatten().filter(|&&c| c != 0).count(), 30);
+        assert!(validate_solution(&full).is_ok());
+        assert!(has_unique_solution(&clues));
+
+        let solved = solve_puzzle(&clues).unwrap();
+        assert_eq!(solved, full);
+
+        let public_input = Vec::<Vec<Fp>>::from(Grid(clues)).concat();
+        let circuit = MyCircuit::new(solution_witness(&clues, &solved)).unwrap();
+        let proof = prove(k, &circuit, &public_input).unwrap();
+        let vk = keygen_vk(&Params::new(k), &circuit).unwrap();
+        assert!(verify(k, &vk, &public_input, &proof).is_ok());
+    }
+
+    /// Records the name of every span created while it is the default
+    /// subscriber.
+    #[cfg(feature = "trace")]
+    struct SpanNames(std::sync::Mutex<Vec<&'static str>>);
+
+    #[cfg(feature = "trace")]
+    impl tracing::Subscriber for SpanNames {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            let mut names = self.0.lock().unwrap();
+            names.push(span.metadata().name());
+            tracing::span::Id::from_u64(names.len() as u64)
+        }
+
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, _: &tracing::Event<'_>) {}
+
+        fn enter(&self, _: &tracing::span::Id) {}
+
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[cfg(feature = "trace")]
+    #[test]
+    fn proving_emits_spans() {
+        let k = 5;
+        let (public_input, circuit) = four_by_four();
+
+        let subscriber = std::sync::Arc::new(SpanNames(Default::default()));
+        tracing::subscriber::with_default(subscriber.clone(), || {
+            let proof = prove(k, &circuit, &public_input).unwrap();
+            let vk = keygen_vk(&Params::new(k), &circuit).unwrap();
+            assert!(verify(k, &vk, &public_input, &proof).is_ok());
+        });
+
+        let names = subscriber.0.lock().unwrap();
+        for name in ["configure", "assign", "keygen", "prove", "verify"] {
+            assert!(names.contains(&name), "no {} span in {:?}", name, names);
+        }
+    }
+
+    #[test]
+    fn build_then_prove() {
+        let k = 7;
+        let (clues, _) = example_arrays();
+
+        let (circuit, public) = MyCircuit::<Fp>::build(&clues).unwrap();
+        let proof = prove(k, &circuit, &public[0]).unwrap();
+        let vk = keygen_vk(&Params::new(k), &circuit).unwrap();
+        assert!(verify(k, &vk, &public[0], &proof).is_ok());
+    }
+
+    #[test]
+    fn uniqueness_is_certified_off_circuit() {
+        let proper = [
+            [5, 3, 0, 0, 7, 0, 0, 0, 0],
+            [6, 0, 0, 1, 9, 5, 0, 0, 0],
+            [0, 9, 8, 0, 0, 0, 0, 6, 0],
+            [8, 0, 0, 0, 6, 0, 0, 0, 3],
+            [4, 0, 0, 8, 0, 3, 0, 0, 1],
+            [7, 0, 0, 0, 2, 0, 0, 0, 6],
+            [0, 6, 0, 0, 0, 0, 2, 8, 0],
+            [0, 0, 0, 4, 1, 9, 0, 0, 5],
+            [0, 0, 0, 0, 8, 0, 0, 7, 9],
+        ];
+        let (proof, certificate) = prove_uniqueness(&proper).unwrap();
+        assert!(verify_uniqueness(&proper, &certificate));
+        assert_eq!(Some(certificate.solution), solve_puzzle(&proper));
+
+        let (circuit, public) = MyCircuit::<Fp>::build(&proper).unwrap();
+        let k = min_k(&circuit);
+        let vk = keygen_vk(&Params::new(k), &circuit).unwrap();
+        assert!(verify(k, &vk, &public[0], &proof).is_ok());
+
+        // too small a budget, or another grid, does not pass
+        let mut short = certificate.clone();
+        short.nodes -= 1;
+        assert!(!verify_uniqueness(&proper, &short));
+        let mut other = certificate.clone();
+        other.solution[0].swap(2, 3);
+        assert!(!verify_uniqueness(&proper, &other));
+
+        // the crate's example puzzle has thousands of completions
+        let (ambiguous, _) = example_arrays();
+        assert!(matches!(
+            prove_uniqueness(&ambiguous),
+            Err(SudokuError::NotUnique)
+        ));
+        // a real completion with an unlimited budget still finds a second
+        let guess = UniquenessCertificate {
+            solution: solve_puzzle(&ambiguous).unwrap(),
+            nodes: usize::MAX,
+        };
+        assert!(!verify_uniqueness(&ambiguous, &guess));
+    }
+
+    #[test]
+    fn out_of_range_clues_are_rejected_up_front() {
+        let k = 7;
+        let (puzzle, _) = generate_puzzle(&mut ChaCha20Rng::seed_from_u64(305), 30);
+        let (circuit, mut public) = MyCircuit::<Fp>::build(&puzzle).unwrap();
+        let vk = keygen_vk(&Params::new(k), &circuit).unwrap();
+        let proof = prove(k, &circuit, &public[0]).unwrap();
+
+        public[0][40] = Fp::from(11);
+        let out_of_range = |result: Result<_, SudokuError>| {
+            matches!(
+                result,
+                Err(SudokuError::ValueOutOfRange {
+                    row: 4,
+                    col: 4,
+                    value: 11,
+                    max: 9
+                })
+            )
+        };
+        assert!(out_of_range(prove(k, &circuit, &public[0]).map(drop)));
+        assert!(out_of_range(verify(k, &vk, &public[0], &proof)));
+        let verifier = SudokuVerifier::new(k, &Vec::<Vec<Fp>>::from(Grid(puzzle))).unwrap();
+        assert!(out_of_range(verifier.verify(&public[0], &proof)));
+
+        let mut clues = puzzle;
+        clues[4][4] = 11;
+        assert!(out_of_range(MyCircuit::<Fp>::build(&clues).map(drop)));
+    }
+
+    #[test]
+    fn proofs_of_one_witness_are_blinded() {
+        let k = 5;
+
+        let (public_input, circuit) = four_by_four();
+
+        let first = prove(k, &circuit, &public_input).unwrap();
+        let second = prove(k, &circuit, &public_input).unwrap();
+        assert_ne!(first, second);
+
+        let vk = keygen_vk(&Params::new(k), &circuit).unwrap();
+        assert!(verify(k, &vk, &public_input, &first).is_ok());
+        assert!(verify(k, &vk, &public_input, &second).is_ok());
+    }
+
+    #[test]
+    fn min_k_fits_the_example() {
+        let (public_grid, solution) = example_grids();
+
+        let public_input = to_field_grid::<Fp>(public_grid).concat();
+        let circuit = MyCircuit::new(to_field_grid(solution)).unwrap();
+
+        let k = min_k(&circuit);
+        let proof = prove(k, &circuit, &public_input).unwrap();
+        let vk = keygen_vk(&Params::new(k), &circuit).unwrap();
+        assert!(verify(k, &vk, &public_input, &proof).is_ok());
+
+        assert!(prove(k - 1, &circuit, &public_input).is_err());
+    }
+
+    #[test]
+    fn is_satisfied_never_panics() {
+        let (public_grid, solution) = example_grids();
+
+        let public_input = packed_instance(&to_field_grid(public_grid));
+        let circuit = MyCircuit::new(to_field_grid(solution)).unwrap();
+        assert!(is_satisfied(7, &circuit, &public_input));
+
+        let mut wrong = public_input.clone();
+        wrong[0][2] += Fp::one();
+        assert!(!is_satisfied(7, &circuit, &wrong));
+
+        // too many columns, none, and more values than the column has rows
+        let mut extra = public_input.clone();
+        extra.push(vec![Fp::zero()]);
+        assert!(!is_satisfied(7, &circuit, &extra));
+        assert!(!is_satisfied(7, &circuit, &[]));
+        assert!(!is_satisfied(7, &circuit, &[vec![Fp::zero(); 200]]));
+
+        // too small to fit the board, or to have any usable rows at all
+        assert!(!is_satisfied(6, &circuit, &public_input));
+        assert!(!is_satisfied(1, &circuit, &public_input));
+    }
+
+    #[test]
+    fn stream_proof_through_a_cursor() {
+        let (public_grid, solution) = example_grids();
+
+        let k = 7;
+        let public_input = to_field_grid::<Fp>(public_grid).concat();
+        let circuit = MyCircuit::new(to_field_grid(solution)).unwrap();
+
+        let mut cursor = Cursor::new(Vec::new());
+        prove_to(k, &circuit, &public_input, &mut cursor).unwrap();
+
+        cursor.set_position(0);
+        let vk = keygen_vk(&Params::new(k), &circuit).unwrap();
+        assert!(verify_from(k, &vk, &public_input, &mut cursor).is_ok());
+
+        // a truncated stream cannot be read to the end
+        let mut bytes = cursor.into_inner();
+        bytes.truncate(bytes.len() / 2);
+        assert!(verify_from(k, &vk, &public_input, Cursor::new(bytes)).is_err());
+    }
+}
@@ -0,0 +1,720 @@
+//! Plain-Rust sudoku logic over `u64` grids (zero for blanks). Nothing here
+//! depends on halo2; it exists to help build witnesses and drive UIs.
+
+use crate::{circuit::Cell, diagnostics::GridLocation, error::SudokuError};
+use rand_core::RngCore;
+
+/// Coordinates of the 27 units of a 9x9 grid: the nine rows, then the nine
+/// columns, then the nine 3x3 boxes left to right, top to bottom, each as
+/// its cells in reading order. Unit `i` is the one
+/// [`GridLocation`](crate::GridLocation) numbers `i % 9`, so variant
+/// constraints can be written once over every unit.
+pub fn units() -> impl Iterator<Item = [Cell; 9]> {
+    const INDICES: [usize; 9] = [0, 1, 2, 3, 4, 5, 6, 7, 8];
+
+    let rows = (0..9).map(|row| INDICES.map(|col| (row, col)));
+    let cols = (0..9).map(|col| INDICES.map(|row| (row, col)));
+    let boxes = (0..9).map(|b| INDICES.map(|i| ((b / 3) * 3 + i / 3, (b % 3) * 3 + i % 3)));
+    rows.chain(cols).chain(boxes)
+}
+
+/// Fails with [`SudokuError::BadDimensions`] unless `grid` is 9x9, which
+/// every helper here indexes without further checks.
+fn check_dimensions(grid: &[Vec<u64>]) -> Result<(), SudokuError> {
+    let rows = grid.len();
+    let cols = grid.iter().map(Vec::len).find(|&len| len != 9).unwrap_or(9);
+    if rows != 9 || cols != 9 {
+        return Err(SudokuError::BadDimensions { rows, cols });
+    }
+    Ok(())
+}
+
+/// Digits that could still go in the blank at `(row, col)` without
+/// repeating a value in its row, column, or box.
+fn cell_candidates(grid: &[Vec<u64>], row: usize, col: usize) -> Vec<u64> {
+    let (box_row, box_col) = ((row / 3) * 3, (col / 3) * 3);
+    (1..=9)
+        .filter(|&digit| {
+            (0..9).all(|i| {
+                grid[row][i] != digit
+                    && grid[i][col] != digit
+                    && grid[box_row + i / 3][box_col + i % 3] != digit
+            })
+        })
+        .collect()
+}
+
+/// Finds one blank whose value follows from a naked single (it has one
+/// candidate) or a hidden single (it is the only place left for a digit in
+/// some unit).
+fn next_single(grid: &[Vec<u64>]) -> Option<(usize, usize, u64)> {
+    for row in 0..9 {
+        for col in 0..9 {
+            if grid[row][col] == 0 {
+                if let [digit] = cell_candidates(grid, row, col)[..] {
+                    return Some((row, col, digit));
+                }
+            }
+        }
+    }
+
+    for unit in units() {
+        for digit in 1..=9 {
+            if unit.iter().any(|&(row, col)| grid[row][col] == digit) {
+                continue;
+            }
+            let mut places = unit.iter().filter(|&&(row, col)| {
+                grid[row][col] == 0 && cell_candidates(grid, row, col).contains(&digit)
+            });
+            if let (Some(&(row, col)), None) = (places.next(), places.next()) {
+                return Some((row, col, digit));
+            }
+        }
+    }
+
+    None
+}
+
+/// Returns the blanks of `puzzle` whose values are forced by repeatedly
+/// applying naked-single and hidden-single logic, as `(row, col, value)` in
+/// the order they were deduced. No guessing is involved, so every entry is
+/// part of any solution the puzzle has. `None` unless `puzzle` is 9x9.
+pub fn forced_cells(puzzle: &[Vec<u64>]) -> Option<Vec<(usize, usize, u64)>> {
+    check_dimensions(puzzle).ok()?;
+    let mut grid = puzzle.to_vec();
+    let mut forced = Vec::new();
+
+    while let Some((row, col, digit)) = next_single(&grid) {
+        grid[row][col] = digit;
+        forced.push((row, col, digit));
+    }
+
+    Some(forced)
+}
+
+/// Pencil marks for `puzzle`: for every cell, indexed `[row][col]`, the
+/// digits still possible once [`forced_cells`] has filled in every single.
+/// Givens and forced cells list just their value; a blank whose list is
+/// empty means the puzzle has no solution. `None` unless `puzzle` is 9x9.
+pub fn candidates(puzzle: &[Vec<u64>]) -> Option<Vec<Vec<Vec<u64>>>> {
+    let mut grid = puzzle.to_vec();
+    for (row, col, digit) in forced_cells(puzzle)? {
+        grid[row][col] = digit;
+    }
+
+    let marks = (0..9)
+        .map(|row| {
+            (0..9)
+                .map(|col| match grid[row][col] {
+                    0 => cell_candidates(&grid, row, col),
+                    value => vec![value],
+                })
+                .collect()
+        })
+        .collect();
+    Some(marks)
+}
+
+/// Names unit `index` of [`units`], e.g. `"row 2"` or `"box 4"`.
+fn unit_name(index: usize) -> String {
+    let kind = ["row", "column", "box"][index / 9];
+    format!("{} {}", kind, index % 9)
+}
+
+/// The first contradiction among the filled cells of `grid`: a value that is
+/// not a digit, or a digit repeated within a unit.
+fn filled_contradiction(grid: &[Vec<u64>]) -> Option<String> {
+    for (row, values) in grid.iter().enumerate() {
+        for (col, &value) in values.iter().enumerate() {
+            if value > 9 {
+                return Some(format!(
+                    "cell ({},{}) holds {}, not a digit",
+                    row, col, value
+                ));
+            }
+        }
+    }
+
+    for (index, unit) in units().enumerate() {
+        let mut seen = [false; 10];
+        for (row, col) in unit {
+            let value = grid[row][col] as usize;
+            if value != 0 && std::mem::replace(&mut seen[value], true) {
+                return Some(format!("duplicate {} in {}", value, unit_name(index)));
+            }
+        }
+    }
+
+    None
+}
+
+/// Checks that `grid` is a complete, valid solution: every cell holds a digit
+/// in `1..=9` and every row, column and 3x3 box holds each digit once. Cells
+/// are checked in row-major order before units, so a blank or out-of-range
+/// cell is reported ahead of any duplicate.
+pub fn validate_solution(grid: &[[u8; 9]; 9]) -> Result<(), SudokuError> {
+    for (row, values) in grid.iter().enumerate() {
+        for (col, &value) in values.iter().enumerate() {
+            match value {
+                0 => return Err(SudokuError::Incomplete { row, col }),
+                1..=9 => {}
+                _ => {
+                    return Err(SudokuError::ValueOutOfRange {
+                        row,
+                        col,
+                        value: value.into(),
+                        max: 9,
+                    })
+                }
+            }
+        }
+    }
+
+    for (index, unit) in units().enumerate() {
+        let mut seen: [Option<(usize, usize)>; 10] = [None; 10];
+        for (row, col) in unit {
+            let value = grid[row][col];
+            if let Some(first) = seen[value as usize].replace((row, col)) {
+                let number = index % 9;
+                return Err(SudokuError::DuplicateInUnit {
+                    unit: match index / 9 {
+                        0 => GridLocation::Row(number),
+                        1 => GridLocation::Column(number),
+                        _ => GridLocation::Box(number),
+                    },
+                    value: value.into(),
+                    first,
+                    second: (row, col),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Solves `puzzle` by filling in every [`forced_cells`] single, then
+/// backtracking, always branching on the blank with the fewest candidates.
+/// Returns `None` if `puzzle` is not 9x9, the givens conflict or no solution
+/// exists; if several exist, the first one found.
+pub fn solve(puzzle: &[Vec<u64>]) -> Option<Vec<Vec<u64>>> {
+    fn search(grid: &mut Vec<Vec<u64>>) -> bool {
+        let (row, col, digits) = match fewest_candidates(grid) {
+            Some(best) => best,
+            None => return true,
+        };
+        for digit in digits {
+            grid[row][col] = digit;
+            if search(grid) {
+                return true;
+            }
+        }
+        grid[row][col] = 0;
+        false
+    }
+
+    check_dimensions(puzzle).ok()?;
+    if filled_contradiction(puzzle).is_some() {
+        return None;
+    }
+    let mut grid = puzzle.to_vec();
+    for (row, col, digit) in forced_cells(puzzle)? {
+        grid[row][col] = digit;
+    }
+    search(&mut grid).then_some(grid)
+}
+
+/// The blank with the fewest candidates and those candidates, or `None` if
+/// the grid is full.
+fn fewest_candidates(grid: &[Vec<u64>]) -> Option<(usize, usize, Vec<u64>)> {
+    let mut best: Option<(usize, usize, Vec<u64>)> = None;
+    for row in 0..9 {
+        for col in 0..9 {
+            if grid[row][col] != 0 {
+                continue;
+            }
+            let digits = cell_candidates(grid, row, col);
+            let fewer = match &best {
+                Some((_, _, fewest)) => digits.len() < fewest.len(),
+                None => true,
+            };
+            if fewer {
+                best = Some((row, col, digits));
+            }
+        }
+    }
+    best
+}
+
+/// Counts the completions of `grid` up to `limit`, branching like [`solve`]
+/// on the blank with the fewest candidates and stopping once `limit` are
+/// found. `nodes` counts the search nodes visited; past `budget` the search
+/// gives up with `None`. The first completion found is kept in `first`.
+fn count_solutions(
+    grid: &mut Vec<Vec<u64>>,
+    limit: usize,
+    budget: usize,
+    nodes: &mut usize,
+    first: &mut Option<Vec<Vec<u64>>>,
+) -> Option<usize> {
+    *nodes += 1;
+    if *nodes > budget {
+        return None;
+    }
+    let (row, col, digits) = match fewest_candidates(grid) {
+        Some(best) => best,
+        None => {
+            first.get_or_insert_with(|| grid.clone());
+            return Some(1);
+        }
+    };
+    let mut found = 0;
+    for digit in digits {
+        grid[row][col] = digit;
+        found += count_solutions(grid, limit - found, budget, nodes, first)?;
+        if found >= limit {
+            break;
+        }
+    }
+    grid[row][col] = 0;
+    Some(found)
+}
+
+pub(crate) fn to_vec_grid(clues: &[[u8; 9]; 9]) -> Vec<Vec<u64>> {
+    clues
+        .iter()
+        .map(|row| row.iter().map(|&value| u64::from(value)).collect())
+        .collect()
+}
+
+/// Whether `clues` (zero for blanks) has exactly one solution, i.e. is a
+/// proper puzzle. Backtracks like [`solve`] but stops counting at two, so it
+/// stays quick on puzzles with many solutions. Givens that already conflict
+/// count as no solution.
+pub fn has_unique_solution(clues: &[[u8; 9]; 9]) -> bool {
+    let mut grid = to_vec_grid(clues);
+    if filled_contradiction(&grid).is_some() {
+        return false;
+    }
+    count_solutions(&mut grid, 2, usize::MAX, &mut 0, &mut None) == Some(1)
+}
+
+/// Evidence from an exhaustive search that a puzzle has exactly one
+/// solution: the solution itself and the number of nodes the search needed
+/// to rule out any other.
+///
+/// The SNARK only proves that the prover knows *a* solution; it says
+/// nothing about whether there are others. A certificate is checked on the
+/// host by [`verify_uniqueness`], outside the proof system, so a verifier
+/// who needs uniqueness must run that check themselves.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UniquenessCertificate {
+    pub solution: [[u8; 9]; 9],
+    pub nodes: usize,
+}
+
+/// Searches every branch of `clues` and returns a [`UniquenessCertificate`]
+/// if exactly one solution exists. Conflicting or unsolvable givens fail
+/// with [`SudokuError::Unsolvable`], several solutions with
+/// [`SudokuError::NotUnique`].
+pub fn uniqueness_certificate(clues: &[[u8; 9]; 9]) -> Result<UniquenessCertificate, SudokuError> {
+    let mut grid = to_vec_grid(clues);
+    if filled_contradiction(&grid).is_some() {
+        return Err(SudokuError::Unsolvable);
+    }
+    let mut nodes = 0;
+    let mut first = None;
+    match count_solutions(&mut grid, 2, usize::MAX, &mut nodes, &mut first) {
+        Some(0) => Err(SudokuError::Unsolvable),
+        Some(1) => {
+            let mut solution = [[0; 9]; 9];
+            for (row, values) in solution.iter_mut().zip(first.unwrap()) {
+                for (cell, value) in row.iter_mut().zip(values) {
+                    *cell = value as u8;
+                }
+            }
+            Ok(UniquenessCertificate { solution, nodes })
+        }
+        _ => Err(SudokuError::NotUnique),
+    }
+}
+
+/// Checks `certificate` against `clues` by re-running the search with a
+/// budget of `certificate.nodes` nodes: true only if it finishes within the
+/// budget, finds exactly one solution and that solution is the
+/// certificate's. The search is deterministic, so an honest certificate
+/// always passes; this runs on the host, not in the circuit.
+pub fn verify_uniqueness(clues: &[[u8; 9]; 9], certificate: &UniquenessCertificate) -> bool {
+    let mut grid = to_vec_grid(clues);
+    if filled_contradiction(&grid).is_some() {
+        return false;
+    }
+    let mut first = None;
+    let found = count_solutions(&mut grid, 2, certificate.nodes, &mut 0, &mut first);
+    found == Some(1) && first == Some(to_vec_grid(&certificate.solution))
+}
+
+/// Like [`solve`], for fixed-size grids such as the output of
+/// [`parse_puzzle`](crate::io::parse_puzzle). A puzzle with several solutions
+/// gets one of them, with no guarantee which.
+pub fn solve_puzzle(clues: &[[u8; 9]; 9]) -> Option<[[u8; 9]; 9]> {
+    let solution = solve(&to_vec_grid(clues))?;
+
+    let mut grid = [[0; 9]; 9];
+    for (row, values) in grid.iter_mut().zip(solution) {
+        for (cell, value) in row.iter_mut().zip(values) {
+            *cell = value as u8;
+        }
+    }
+    Some(grid)
+}
+
+/// Shuffles `items` in place with a Fisher-Yates pass driven by `rng`.
+fn shuffle<T>(rng: &mut impl RngCore, items: &mut [T]) {
+    for i in (1..items.len()).rev() {
+        let j = rng.next_u32() as usize % (i + 1);
+        items.swap(i, j);
+    }
+}
+
+/// A random permutation of `0..9` that moves whole blocks of three and
+/// shuffles inside each, e.g. `[4, 3, 5, 0, 2, 1, 8, 6, 7]`: a reordering of
+/// rows (or columns) that keeps every box together.
+fn band_order(rng: &mut impl RngCore) -> [usize; 9] {
+    let mut bands = [0, 1, 2];
+    shuffle(rng, &mut bands);
+    let mut order = [0; 9];
+    for (slot, band) in order.chunks_mut(3).zip(bands) {
+        let mut lines = [band * 3, band * 3 + 1, band * 3 + 2];
+        shuffle(rng, &mut lines);
+        slot.copy_from_slice(&lines);
+    }
+    order
+}
+
+/// A random puzzle with `clues_to_keep` givens (all 81 if more), returned as
+/// `(clues, full_solution)` with zero for blanks in `clues`.
+///
+/// The solution is the solver's completion of an empty board with its digits
+/// relabelled and its rows, columns, bands and stacks permuted, all of which
+/// keep it valid. Cells are then removed in random order, skipping any whose
+/// removal would leave several solutions (see [`has_unique_solution`]); if
+/// that stalls above `clues_to_keep`, the rest are removed regardless, so
+/// asking for very few clues gives an ambiguous puzzle rather than too many
+/// clues.
+pub fn generate_puzzle(
+    rng: &mut impl RngCore,
+    clues_to_keep: usize,
+) -> ([[u8; 9]; 9], [[u8; 9]; 9]) {
+    let base = solve_puzzle(&[[0; 9]; 9]).expect("the empty board has solutions");
+    let mut digits = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+    shuffle(rng, &mut digits);
+    let rows = band_order(rng);
+    let cols = band_order(rng);
+
+    let mut full = [[0; 9]; 9];
+    for (row, cells) in full.iter_mut().enumerate() {
+        for (col, cell) in cells.iter_mut().enumerate() {
+            *cell = digits[usize::from(base[rows[row]][cols[col]]) - 1];
+        }
+    }
+
+    let mut cells: Vec<(usize, usize)> = (0..81).map(|i| (i / 9, i % 9)).collect();
+    shuffle(rng, &mut cells);
+    let mut clues = full;
+    let mut kept = 81;
+    for &(row, col) in &cells {
+        if kept <= clues_to_keep {
+            break;
+        }
+        clues[row][col] = 0;
+        if has_unique_solution(&clues) {
+            kept -= 1;
+        } else {
+            clues[row][col] = full[row][col];
+        }
+    }
+    for &(row, col) in &cells {
+        if kept <= clues_to_keep {
+            break;
+        }
+        if clues[row][col] != 0 {
+            clues[row][col] = 0;
+            kept -= 1;
+        }
+    }
+    (clues, full)
+}
+
+/// Explains why `puzzle` has no solution, or returns `None` if [`solve`]
+/// finds one. The message names the first contradiction found: a value that
+/// is not a digit, a digit repeated among the givens (`"duplicate 7 in row
+/// 2"`), or, once [`forced_cells`] has propagated every single, a blank with
+/// no candidates (`"cell (3,4) has no candidates"`) or a digit with no place
+/// left in a unit. Row, column and box numbers start at 0. A grid that is
+/// not 9x9 is explained as such.
+pub fn explain_unsolvable(puzzle: &[Vec<u64>]) -> Option<String> {
+    if let Err(e) = check_dimensions(puzzle) {
+        return Some(e.to_string());
+    }
+    if let Some(contradiction) = filled_contradiction(puzzle) {
+        return Some(contradiction);
+    }
+    if solve(puzzle).is_some() {
+        return None;
+    }
+
+    let marks = candidates(puzzle)?;
+    for (row, cells) in marks.iter().enumerate() {
+        for (col, digits) in cells.iter().enumerate() {
+            if digits.is_empty() {
+                return Some(format!("cell ({},{}) has no candidates", row, col));
+            }
+        }
+    }
+
+    for (index, unit) in units().enumerate() {
+        for digit in 1..=9 {
+            if !unit
+                .iter()
+                .any(|&(row, col)| marks[row][col].contains(&digit))
+            {
+                return Some(format!("{} has no place for {}", unit_name(index), digit));
+            }
+        }
+    }
+
+    Some("no solution exists, though propagating singles finds no direct contradiction".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        candidates, explain_unsolvable, forced_cells, has_unique_solution, solve, solve_puzzle,
+        to_vec_grid, units, validate_solution,
+    };
+    use crate::{
+        diagnostics::GridLocation,
+        error::SudokuError,
+        fixtures::{example_arrays, example_grids},
+        io::merge_grid,
+    };
+    use rand_core::RngCore;
+
+    #[test]
+    fn every_cell_is_in_three_units() {
+        let units: Vec<_> = units().collect();
+        assert_eq!(units.len(), 27);
+
+        let mut count = [[0; 9]; 9];
+        for unit in &units {
+            for &(row, col) in unit {
+                count[row][col] += 1;
+            }
+        }
+        assert_eq!(count, [[3; 9]; 9]);
+        assert_eq!(units[9][8], (8, 0));
+        assert_eq!(units[22][4], (4, 4));
+    }
+
+    /// The shared example's completed grid.
+    fn example_full() -> Vec<Vec<u64>> {
+        let (clues, witness) = example_arrays();
+        to_vec_grid(&merge_grid(&clues, &witness))
+    }
+
+    #[test]
+    fn naked_single_is_forced() {
+        let mut puzzle = example_full();
+        puzzle[4][4] = 0;
+
+        assert_eq!(forced_cells(&puzzle), Some(vec![(4, 4, 9)]));
+    }
+
+    #[test]
+    fn candidates_after_propagation() {
+        let mut puzzle = example_full();
+        puzzle[4][4] = 0;
+        assert_eq!(candidates(&puzzle).unwrap()[4][4], vec![9]);
+
+        let mut sparse = vec![vec![0; 9]; 9];
+        sparse[0][0] = 1;
+        sparse[0][1] = 2;
+        sparse[4][5] = 3;
+
+        let marks = candidates(&sparse).unwrap();
+        assert_eq!(marks[0][0], vec![1]);
+        // row 0 rules out 1 and 2, column 5 rules out 3
+        assert_eq!(marks[0][5], vec![4, 5, 6, 7, 8, 9]);
+        // nothing shares a unit with (8, 8)
+        assert_eq!(marks[8][8], (1..=9).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn forced_cells_agree_with_solution() {
+        let (puzzle, _) = example_grids();
+        let full = example_full();
+
+        let forced = forced_cells(&puzzle).unwrap();
+        assert!(!forced.is_empty());
+        for (row, col, value) in forced {
+            assert_eq!(puzzle[row][col], 0);
+            assert_eq!(full[row][col], value);
+        }
+    }
+
+    #[test]
+    fn solve_completes_the_example() {
+        let mut puzzle = example_full();
+        for (row, values) in puzzle.iter_mut().enumerate() {
+            for (col, value) in values.iter_mut().enumerate() {
+                if (row + col) % 3 == 0 {
+                    *value = 0;
+                }
+            }
+        }
+
+        assert_eq!(solve(&puzzle), Some(example_full()));
+    }
+
+    #[test]
+    fn unsolvable_puzzles_are_explained() {
+        let mut puzzle = example_full();
+        puzzle[4][4] = 0;
+        assert_eq!(explain_unsolvable(&puzzle), None);
+
+        let mut duplicate = puzzle.clone();
+        duplicate[2][0] = 0;
+        duplicate[2][5] = 7;
+        assert_eq!(
+            explain_unsolvable(&duplicate).as_deref(),
+            Some("duplicate 7 in row 2")
+        );
+
+        // (4,4) can only be 9, but 9 is moved into its box
+        let mut stuck = vec![vec![0; 9]; 9];
+        for (col, digit) in [(0, 1), (1, 2), (2, 3), (6, 4), (7, 5), (8, 6)] {
+            stuck[4][col] = digit;
+        }
+        stuck[0][4] = 7;
+        stuck[8][4] = 8;
+        stuck[3][3] = 9;
+        assert_eq!(
+            explain_unsolvable(&stuck).as_deref(),
+            Some("cell (4,4) has no candidates")
+        );
+        assert_eq!(solve(&stuck), None);
+    }
+
+    #[test]
+    fn misshapen_grids_are_rejected() {
+        let mut short_row = example_full();
+        short_row[3].pop();
+        let grids = [vec![], example_full()[..8].to_vec(), short_row];
+        for grid in &grids {
+            assert_eq!(forced_cells(grid), None);
+            assert_eq!(candidates(grid), None);
+            assert_eq!(solve(grid), None);
+        }
+        assert_eq!(
+            explain_unsolvable(&grids[1]).as_deref(),
+            Some("grid does not match the board: 8 rows, a row of 9 cells")
+        );
+        assert_eq!(
+            explain_unsolvable(&grids[2]).as_deref(),
+            Some("grid does not match the board: 9 rows, a row of 8 cells")
+        );
+    }
+
+    fn to_array(grid: &[Vec<u64>]) -> [[u8; 9]; 9] {
+        let mut array = [[0; 9]; 9];
+        for (row, values) in array.iter_mut().zip(grid) {
+            for (cell, &value) in row.iter_mut().zip(values) {
+                *cell = value as u8;
+            }
+        }
+        array
+    }
+
+    #[test]
+    fn validate_solution_reports_first_problem() {
+        let (clues, witness) = example_arrays();
+        let full = merge_grid(&clues, &witness);
+        assert!(validate_solution(&full).is_ok());
+
+        let mut blank = full;
+        blank[3][4] = 0;
+        assert!(matches!(
+            validate_solution(&blank),
+            Err(SudokuError::Incomplete { row: 3, col: 4 })
+        ));
+
+        let mut large = full;
+        large[8][0] = 10;
+        assert!(matches!(
+            validate_solution(&large),
+            Err(SudokuError::ValueOutOfRange {
+                row: 8,
+                col: 0,
+                value: 10,
+                max: 9
+            })
+        ));
+
+        // (0,0) = 5 copied over (0,2) repeats 5 in row 0 and box 0
+        let mut duplicate = full;
+        duplicate[0][2] = 5;
+        assert!(matches!(
+            validate_solution(&duplicate),
+            Err(SudokuError::DuplicateInUnit {
+                unit: GridLocation::Row(0),
+                value: 5,
+                first: (0, 0),
+                second: (0, 2),
+            })
+        ));
+    }
+
+    #[test]
+    fn solve_puzzle_returns_a_valid_completion() {
+        let (clues, _) = example_arrays();
+
+        // this puzzle has many solutions; any will do
+        let full = solve_puzzle(&clues).unwrap();
+        assert!(validate_solution(&full).is_ok());
+        for (givens, cells) in clues.iter().zip(&full) {
+            for (&given, &cell) in givens.iter().zip(cells) {
+                assert!(given == 0 || given == cell);
+            }
+        }
+
+        let mut conflicting = clues;
+        conflicting[0][0] = 1;
+        assert_eq!(solve_puzzle(&conflicting), None);
+    }
+
+    #[test]
+    fn unique_solution_check() {
+        let proper = to_array(&[
+            vec![5, 3, 0, 0, 7, 0, 0, 0, 0],
+            vec![6, 0, 0, 1, 9, 5, 0, 0, 0],
+            vec![0, 9, 8, 0, 0, 0, 0, 6, 0],
+            vec![8, 0, 0, 0, 6, 0, 0, 0, 3],
+            vec![4, 0, 0, 8, 0, 3, 0, 0, 1],
+            vec![7, 0, 0, 0, 2, 0, 0, 0, 6],
+            vec![0, 6, 0, 0, 0, 0, 2, 8, 0],
+            vec![0, 0, 0, 4, 1, 9, 0, 0, 5],
+            vec![0, 0, 0, 0, 8, 0, 0, 7, 9],
+        ]);
+        assert!(has_unique_solution(&proper));
+
+        // the crate's example puzzle has thousands of completions
+        let (loose, _) = example_arrays();
+        assert!(!has_unique_solution(&loose));
+
+        // a second 5 in row 0
+        let mut contradictory = proper;
+        contradictory[0][8] = 5;
+        assert!(!has_unique_solution(&contradictory));
+    }
+}
@@ -0,0 +1,51 @@
+//! Loading puzzles stored as 81-character lines in SQLite.
+
+use rusqlite::Connection;
+
+use crate::{error::SudokuError, io::parse_line};
+
+/// Runs `query` and parses the first column of every returned row as a puzzle
+/// line (see [`parse_line`]), in row order.
+pub fn load_from_db(conn: &Connection, query: &str) -> Result<Vec<Vec<Vec<u64>>>, SudokuError> {
+    let mut statement = conn.prepare(query)?;
+    let lines = statement.query_map([], |row| row.get::<_, String>(0))?;
+
+    lines.map(|line| parse_line(&line?)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::load_from_db;
+    use crate::fixtures::{example_grids, example_lines};
+    use rusqlite::Connection;
+
+    #[test]
+    fn loads_puzzle_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+        let (example, _) = example_lines();
+        conn.execute_batch(&format!(
+            "CREATE TABLE puzzles (id INTEGER PRIMARY KEY, line TEXT NOT NULL);
+             INSERT INTO puzzles (line) VALUES
+                 ('{}'),
+                 ('000000000000000000000000000000000000000000000000000000000000000000000000000000009');",
+            example
+        ))
+        .unwrap();
+
+        let puzzles = load_from_db(&conn, "SELECT line FROM puzzles ORDER BY id").unwrap();
+
+        assert_eq!(puzzles.len(), 2);
+        assert_eq!(puzzles[0], example_grids().0);
+        assert_eq!(puzzles[1][8][8], 9);
+        assert!(puzzles[1][..8].iter().flatten().all(|&v| v == 0));
+    }
+
+    #[test]
+    fn rejects_malformed_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE puzzles (line TEXT); INSERT INTO puzzles VALUES ('12');")
+            .unwrap();
+
+        assert!(load_from_db(&conn, "SELECT line FROM puzzles").is_err());
+    }
+}
@@ -0,0 +1,77 @@
+//! `wasm_bindgen` entry points for proving in the browser, behind the `wasm`
+//! feature. Grids are passed as JSON arrays of nine rows of nine numbers,
+//! zero for blanks, e.g. `[[0,0,1,0,0,4,0,9,0],...]`.
+//!
+//! Nothing here spawns threads, and halo2's parallel loops need none: build
+//! for `wasm32-unknown-unknown` without the `atomics` target feature and they
+//! run on the calling thread, so pages without cross-origin isolation work.
+
+use halo2_proofs::pasta::Fp;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    io::solution_witness,
+    prover::{min_k, placeholder, SudokuProver},
+};
+
+fn parse_grid(json: &str) -> Option<[[u8; 9]; 9]> {
+    serde_json::from_str(json).ok()
+}
+
+/// Keys for `clues` at the smallest `k` the board fits in, so the prover and
+/// the verifier derive the same ones from the puzzle alone.
+fn prover_for(clues: &[[u8; 9]; 9]) -> Option<SudokuProver> {
+    let public: Vec<Vec<Fp>> = clues
+        .iter()
+        .map(|row| row.iter().map(|&v| Fp::from(u64::from(v))).collect())
+        .collect();
+    let k = min_k(&placeholder(&public));
+    SudokuProver::new(k, public).ok()
+}
+
+/// Proves that `solution_json`, a completed grid, solves `clues_json`.
+/// Returns the proof bytes, or an empty vector if either grid is malformed
+/// or the solution does not fit the puzzle.
+#[wasm_bindgen]
+pub fn prove_puzzle(clues_json: &str, solution_json: &str) -> Vec<u8> {
+    let prove = || {
+        let clues = parse_grid(clues_json)?;
+        let full = parse_grid(solution_json)?;
+        prover_for(&clues)?
+            .prove(solution_witness(&clues, &full))
+            .ok()
+    };
+    prove().unwrap_or_default()
+}
+
+/// Checks a proof from [`prove_puzzle`] against the puzzle `clues_json`.
+#[wasm_bindgen]
+pub fn verify_puzzle(clues_json: &str, proof: &[u8]) -> bool {
+    parse_grid(clues_json)
+        .and_then(|clues| prover_for(&clues))
+        .map_or(false, |prover| prover.verify(proof).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{prove_puzzle, verify_puzzle};
+    use crate::{fixtures::example_arrays, io::merge_grid};
+
+    #[test]
+    fn proves_and_verifies_json_grids() {
+        let (clues, witness) = example_arrays();
+        let solution = serde_json::to_string(&merge_grid(&clues, &witness)).unwrap();
+        let clues = serde_json::to_string(&clues).unwrap();
+
+        let proof = prove_puzzle(&clues, &solution);
+        assert!(!proof.is_empty());
+        assert!(verify_puzzle(&clues, &proof));
+
+        // a different puzzle: the 1 at (0, 2) is no longer given
+        let other = clues.replacen("[0,0,1,", "[0,0,0,", 1);
+        assert!(!verify_puzzle(&other, &proof));
+
+        assert!(prove_puzzle("[[1,2,3]]", &solution).is_empty());
+        assert!(!verify_puzzle(&clues, &[]));
+    }
+}